@@ -0,0 +1,40 @@
+use std::fmt::Write;
+use std::path::Path;
+
+//embed langs/en/{final,acceptable}.txt as sorted [u8; 5] arrays, see src/builtin_words.rs
+
+const WORD_LEN: usize = 5;
+
+fn list(name: &str, file: &str) -> String {
+    println!("cargo:rerun-if-changed={}", file);
+    let text = std::fs::read_to_string(file).unwrap_or_else(|e| panic!("{}: {}", file, e));
+    let mut out = format!("pub const {}: &[[u8; {}]] = &[\n", name, WORD_LEN);
+    let mut last = "";
+    for (i, word) in text.lines().map(|x| x.trim()).enumerate() {
+        if word.is_empty() {
+            continue;
+        }
+        //lookups are binary searches, so keep the files clean and sorted
+        if word.len() != WORD_LEN || !word.bytes().all(|c| c.is_ascii_lowercase()) {
+            panic!(
+                "{}:{}: {}: not {} lowercase letters",
+                file,
+                i + 1,
+                word,
+                WORD_LEN
+            );
+        }
+        if word <= last {
+            panic!("{}:{}: {}: not sorted or duplicate", file, i + 1, word);
+        }
+        last = word;
+        writeln!(out, "    *b\"{}\",", word).unwrap();
+    }
+    out + "];\n"
+}
+
+fn main() {
+    let code = list("FINAL", "langs/en/final.txt") + &list("ACCEPTABLE", "langs/en/acceptable.txt");
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("builtin_words.rs");
+    std::fs::write(out, code).unwrap();
+}
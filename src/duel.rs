@@ -0,0 +1,185 @@
+use crate::engine::MAX_TURNS;
+use crate::store::{Game, JsonState, StateStore};
+use crate::words::WordSet;
+use crate::{Cli, PlayFn, ask_answer, play_tty, tui};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+//hot-seat: two players at one terminal take turns setting the word and guessing it
+
+#[derive(Debug, Serialize, Default, Clone)]
+pub struct Tally {
+    pub player: String,
+    pub rounds: usize, //words guessed
+    pub solved: usize,
+    pub guesses: usize, //over solved words
+    pub points: usize,  //MAX_TURNS + 1 - guesses per solved word
+}
+
+pub fn points(game: &Game) -> usize {
+    if game.won() {
+        MAX_TURNS + 1 - game.guesses.len()
+    } else {
+        0
+    }
+}
+
+pub fn head_to_head(games: &[Game], players: &[String]) -> Vec<Tally> {
+    //duel games between these two, as guesser against the other as setter
+    players
+        .iter()
+        .map(|me| {
+            let mut tally = Tally {
+                player: me.clone(),
+                ..Tally::default()
+            };
+            for game in games.iter().filter(|x| {
+                x.mode.as_deref() == Some("duel")
+                    && x.player.as_ref() == Some(me)
+                    && x.opponent
+                        .as_ref()
+                        .is_some_and(|o| o != me && players.contains(o))
+            }) {
+                tally.rounds += 1;
+                if game.won() {
+                    tally.solved += 1;
+                    tally.guesses += game.guesses.len();
+                }
+                tally.points += points(game);
+            }
+            tally
+        })
+        .collect()
+}
+
+pub fn format_table(board: &[Tally]) -> String {
+    let mut out = format!(
+        "{:<16}{:>7}{:>7}{:>8}{:>8}\n",
+        "PLAYER", "ROUNDS", "SOLVED", "AVG", "POINTS"
+    );
+    for x in board.iter() {
+        let avg = if x.solved > 0 {
+            x.guesses as f64 / x.solved as f64
+        } else {
+            0.0
+        };
+        out += &format!(
+            "{:<16}{:>7}{:>7}{:>8.2}{:>8}\n",
+            x.player, x.rounds, x.solved, avg, x.points
+        );
+    }
+    match board {
+        [a, b] if a.points != b.points => {
+            let (lead, other) = if a.points > b.points { (a, b) } else { (b, a) };
+            out += &format!("{} leads {}-{}\n", lead.player, lead.points, other.points);
+        }
+        _ => out += "tied\n",
+    }
+    out
+}
+
+pub fn run(
+    cli: &Cli,
+    players: &[String],
+    rounds: usize,
+    any_word: bool,
+    final_list: &[String],
+    accept_list: &WordSet,
+    mut state_store: Option<Box<dyn StateStore>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let play: PlayFn = if cli.tui { tui::play_tui } else { play_tty };
+    let mut json_data = JsonState::default(); //games of this duel
+    for i in 0..rounds {
+        //roles swap every round
+        let setter = &players[i % 2];
+        let guesser = &players[(i + 1) % 2];
+        println!(
+            "Round {}/{}: {} sets the word, {} looks away.",
+            i + 1,
+            rounds,
+            setter,
+            guesser
+        );
+        let answer = ask_answer(
+            &format!("{}, input the answer word:", setter),
+            final_list,
+            any_word.then_some(accept_list),
+            &cli.language,
+        )
+        .map_err(|e| format!("duel stopped: {}", e))?;
+        tui::clear_screen()?;
+        println!("{}, your turn to guess {}'s word.", guesser, setter);
+
+        let mut round_cli = cli.clone();
+        round_cli.words = Some(answer);
+        round_cli.player = Some(guesser.clone());
+        round_cli.rand_verbos = false;
+        round_cli.days = 1;
+        round_cli.seed = None;
+        round_cli.mode = Some("duel".to_string());
+        play(
+            &round_cli,
+            &mut Vec::new(),
+            &mut BTreeMap::new(),
+            final_list,
+            accept_list,
+            &mut json_data,
+            0,
+        );
+        let Some(game) = json_data.games.last_mut() else {
+            break;
+        };
+        game.opponent = Some(setter.clone());
+        if let Some(ref mut x) = state_store {
+            x.record_game(game)?;
+        }
+        println!("{} scores {}.\n", guesser, points(game));
+    }
+
+    println!("This duel:");
+    print!("{}", format_table(&head_to_head(&json_data.games, players)));
+    if let Some(ref x) = state_store {
+        println!("\nAll time:");
+        print!("{}", format_table(&head_to_head(&x.load()?.games, players)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(player: &str, opponent: &str, guesses: &[&str], mode: &str) -> Game {
+        Game {
+            answer: "CRANE".to_string(),
+            guesses: guesses.iter().map(|x| x.to_string()).collect(),
+            player: Some(player.to_string()),
+            mode: Some(mode.to_string()),
+            opponent: Some(opponent.to_string()),
+            ..Game::default()
+        }
+    }
+
+    #[test]
+    fn tallies_duels_between_the_two() {
+        let games = [
+            game("ann", "bob", &["SLATE", "CRANE"], "duel"),
+            game("bob", "ann", &["CRANE"], "duel"),
+            game("ann", "bob", &["SLATE"; 6], "duel"),
+            //not between these two, or not a duel
+            game("ann", "cid", &["CRANE"], "duel"),
+            game("ann", "bob", &["CRANE"], "normal"),
+        ];
+        let players = ["ann".to_string(), "bob".to_string()];
+        let board = head_to_head(&games, &players);
+        let row = |x: &Tally| (x.player.clone(), x.rounds, x.solved, x.guesses, x.points);
+        assert_eq!(
+            board.iter().map(row).collect::<Vec<_>>(),
+            [
+                ("ann".to_string(), 2, 1, 2, MAX_TURNS - 1),
+                ("bob".to_string(), 1, 1, 1, MAX_TURNS)
+            ]
+        );
+        assert!(format_table(&board).ends_with("bob leads 6-5\n"));
+    }
+}
@@ -0,0 +1,396 @@
+use crate::lang;
+use crate::words::{Packed, WordSet};
+use std::collections::HashMap;
+
+pub const WORD_LEN: usize = 5;
+pub const MAX_TURNS: usize = 6;
+
+pub fn clock(seconds: u64) -> String {
+    //m:ss for the timers
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+//why a guess is rejected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuessError {
+    Length,
+    NotAlphabetic,
+    NotInList,
+    Difficult, //does not use the previous hints
+}
+
+impl GuessError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            GuessError::Length => "Word must be 5 letters",
+            GuessError::NotAlphabetic => "Only letters are allowed",
+            GuessError::NotInList => "Not in word list",
+            GuessError::Difficult => "Difficult Mode: against the rule",
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        //stable name for machine-readable output
+        match self {
+            GuessError::Length => "length",
+            GuessError::NotAlphabetic => "not_alphabetic",
+            GuessError::NotInList => "not_in_list",
+            GuessError::Difficult => "difficult",
+        }
+    }
+}
+
+pub fn upper(c: char) -> char {
+    //one letter for one letter, ß stays as it is
+    let mut x = c.to_uppercase();
+    match (x.next(), x.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+pub fn status_word(status: char) -> &'static str {
+    //spoken name of a hint, used where color alone is not enough
+    match status {
+        'G' => "correct",
+        'Y' => "present",
+        'R' => "absent",
+        _ => "not tried",
+    }
+}
+
+//one game: answer, accepted guesses and the hints they gave
+#[derive(Clone)]
+pub struct Round {
+    answer: Vec<char>,
+    pub guesses: Vec<String>,
+    pub feedback: Vec<Vec<char>>,       //'G' 'Y' 'R' for each guess
+    pub alphabet: Vec<char>,            //letters of the language, a-z by default
+    pub chracter_status: Vec<char>,     //total status for each letter of alphabet, 'X' unknown
+    pub green_list: Vec<(char, usize)>, //green state letters, use in difficult mood and tips mood
+    pub yellow_list: Vec<char>,
+    pub red_list: Vec<char>,
+    pub difficult: bool,
+    pub max_turns: usize, //MAX_TURNS, more in survival runs
+}
+
+impl Default for Round {
+    fn default() -> Round {
+        Round::new("", false)
+    }
+}
+
+impl Round {
+    pub fn new(answer: &str, difficult: bool) -> Round {
+        Round::with_alphabet(answer, difficult, ('a'..='z').collect())
+    }
+
+    pub fn with_alphabet(answer: &str, difficult: bool, alphabet: Vec<char>) -> Round {
+        Round {
+            answer: answer.trim().to_lowercase().chars().collect(),
+            guesses: Vec::new(),
+            feedback: Vec::new(),
+            chracter_status: vec!['X'; alphabet.len()],
+            alphabet,
+            green_list: Vec::new(),
+            yellow_list: Vec::new(),
+            red_list: Vec::new(),
+            difficult,
+            max_turns: MAX_TURNS,
+        }
+    }
+
+    pub fn answer(&self) -> String {
+        self.answer.iter().collect()
+    }
+
+    pub fn won(&self) -> bool {
+        self.feedback
+            .last()
+            .is_some_and(|x| x.iter().all(|&c| c == 'G'))
+    }
+
+    pub fn finished(&self) -> bool {
+        self.won() || self.guesses.len() >= self.max_turns
+    }
+
+    fn slot(&self, c: char) -> Option<usize> {
+        let c = c.to_lowercase().next().unwrap_or(c);
+        self.alphabet.iter().position(|&x| x == c)
+    }
+
+    pub fn letter_status(&self, c: char) -> char {
+        match self.slot(c) {
+            Some(i) => self.chracter_status[i],
+            None => 'X',
+        }
+    }
+
+    pub fn check(&self, guess: &str, accept_list: &WordSet) -> Result<(), GuessError> {
+        //guess is compared as given, callers decide about case
+        //letters as the packs count them, a mark typed after its base is not one of its own
+        if lang::graphemes(guess).len() != WORD_LEN {
+            return Err(GuessError::Length);
+        }
+        let guess_word_vector: Vec<char> = guess.chars().collect();
+        if !guess_word_vector.iter().all(|c| self.slot(*c).is_some()) {
+            return Err(GuessError::NotAlphabetic);
+        }
+        if !accept_list.contains(guess) {
+            return Err(GuessError::NotInList);
+        }
+        if self.difficult {
+            // difficult mood : don't accord with previous answer
+            for iter in self.green_list.iter() {
+                if guess_word_vector[iter.1] != iter.0 {
+                    return Err(GuessError::Difficult);
+                }
+            }
+            for iter in self.yellow_list.iter() {
+                if !guess.contains(*iter) {
+                    return Err(GuessError::Difficult);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn submit(&mut self, guess: &str, accept_list: &WordSet) -> Result<Vec<char>, GuessError> {
+        //check the guess, then record it and return its status
+        self.check(guess, accept_list)?;
+        Ok(self.record(guess))
+    }
+
+    pub fn record(&mut self, guess: &str) -> Vec<char> {
+        //play a guess without checking it, e.g. replaying a stored game
+        let guess_word_vector: Vec<char> = guess.chars().collect();
+        let s_status = self.score(&guess_word_vector);
+        self.apply(guess, &s_status);
+        s_status
+    }
+
+    pub fn apply(&mut self, guess: &str, s_status: &[char]) {
+        //take a guess with feedback scored elsewhere, e.g. by a race server
+        let guess_word_vector: Vec<char> = guess.chars().collect();
+        for i in 0..WORD_LEN {
+            //renew the character's status
+            let c = guess_word_vector[i];
+            let Some(slot) = self.slot(c) else {
+                continue;
+            };
+            match s_status[i] {
+                'G' => {
+                    self.green_list.push((c, i));
+                    self.chracter_status[slot] = 'G'
+                }
+                'Y' if self.chracter_status[slot] != 'G' => {
+                    self.yellow_list.push(c);
+                    self.chracter_status[slot] = 'Y'
+                }
+                'R' if self.chracter_status[slot] != 'G' && self.chracter_status[slot] != 'Y' => {
+                    self.red_list.push(c);
+                    self.chracter_status[slot] = 'R';
+                }
+                _ => continue,
+            }
+        }
+        self.guesses.push(guess.to_string());
+        self.feedback.push(s_status.to_vec());
+    }
+
+    fn score(&self, guess_word_vector: &[char]) -> Vec<char> {
+        let mut appearance: HashMap<char, i32> = HashMap::new(); // each chracter's number in answer word
+        for c in self.answer.iter() {
+            *appearance.entry(*c).or_insert(0) += 1;
+        }
+        let mut s_status: Vec<char> = ['R'; WORD_LEN].to_vec(); // each chracter's status in guess word,default='R'
+        for i in 0..WORD_LEN {
+            if guess_word_vector[i] == self.answer[i] {
+                s_status[i] = 'G';
+                *appearance.entry(self.answer[i]).or_insert(0) -= 1;
+            } //give 'G'
+        }
+        for i in 0..WORD_LEN {
+            if s_status[i] != 'G'
+                && let Some(&x) = appearance.get(&guess_word_vector[i])
+                && x > 0
+            {
+                //don't overplus
+                s_status[i] = 'Y';
+                *appearance.entry(guess_word_vector[i]).or_insert(0) -= 1; // when reach 0, the number of letter equals,the rest need to be red
+            }
+        }
+        s_status
+    }
+
+    pub fn describe_guess(&self, row: usize) -> String {
+        //"C absent, R present, A correct, N absent, E absent"
+        self.guesses[row]
+            .chars()
+            .zip(self.feedback[row].iter())
+            .map(|(c, &status)| format!("{} {}", upper(c), status_word(status)))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    pub fn describe_alphabet(&self) -> String {
+        //letters grouped by what is known about them
+        let mut parts = Vec::new();
+        for status in ['G', 'Y', 'R', 'X'] {
+            let letters: Vec<String> = self
+                .alphabet
+                .iter()
+                .filter(|&&c| self.letter_status(c) == status)
+                .map(|&c| upper(c).to_string())
+                .collect();
+            if !letters.is_empty() {
+                let mut title = status_word(status).to_string();
+                title[..1].make_ascii_uppercase();
+                parts.push(format!("{}: {}.", title, letters.join(" ")));
+            }
+        }
+        parts.join(" ")
+    }
+
+    pub fn candidates(&self, words: &WordSet) -> Vec<Packed> {
+        //visit all the words and judge if it correspond with previous answers, packed like words
+        let green: Vec<(Option<u8>, usize)> = self
+            .green_list
+            .iter()
+            .map(|x| (words.pack_letter(x.0), x.1))
+            .collect();
+        let yellow: Vec<Option<u8>> = self
+            .yellow_list
+            .iter()
+            .map(|&x| words.pack_letter(x))
+            .collect();
+        let red: Vec<u8> = self
+            .red_list
+            .iter()
+            .filter_map(|&x| words.pack_letter(x))
+            .collect();
+        words
+            .packed()
+            .iter()
+            .copied()
+            .filter(|possible_word| {
+                green.iter().all(|x| x.0 == Some(possible_word[x.1]))
+                    && yellow
+                        .iter()
+                        .all(|x| x.is_some_and(|c| possible_word.contains(&c)))
+                    && !red.iter().any(|c| possible_word.contains(c))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn english(words: &[&str]) -> WordSet {
+        WordSet::new(words.iter().copied(), &('a'..='z').collect::<Vec<char>>())
+    }
+
+    fn feedback(answer: &str, guess: &str) -> String {
+        Round::new(answer, false).record(guess).iter().collect()
+    }
+
+    #[test]
+    fn scores_repeated_letters_once_each() {
+        assert_eq!(feedback("abbey", "bobby"), "YRGRG");
+        assert_eq!(feedback("eerie", "geese"), "RGYRG");
+        assert_eq!(feedback("crane", "eerie"), "RRYRG");
+        //greens use up the letter before yellows are given
+        assert_eq!(feedback("speed", "erase"), "YRRYY");
+        assert_eq!(feedback("crane", "crane"), "GGGGG");
+    }
+
+    #[test]
+    fn letter_status_keeps_the_best_hint() {
+        let mut round = Round::new("geese", false);
+        round.record("eerie");
+        assert_eq!(round.letter_status('e'), 'G');
+        assert_eq!(round.letter_status('r'), 'R');
+        round.record("serve");
+        assert_eq!(round.letter_status('e'), 'G');
+        assert_eq!(round.letter_status('s'), 'Y');
+        assert_eq!(round.letter_status('q'), 'X');
+    }
+
+    #[test]
+    fn rejects_bad_guesses() {
+        let words = english(&["crane", "slate", "abbey"]);
+        let round = Round::new("crane", false);
+        assert_eq!(round.check("cran", &words), Err(GuessError::Length));
+        assert_eq!(round.check("cranes", &words), Err(GuessError::Length));
+        assert_eq!(round.check("cr4ne", &words), Err(GuessError::NotAlphabetic));
+        assert_eq!(round.check("zzzzz", &words), Err(GuessError::NotInList));
+        assert_eq!(round.check("slate", &words), Ok(()));
+    }
+
+    #[test]
+    fn difficult_mode_needs_the_hints() {
+        let words = english(&["crane", "slate", "abbey", "trace"]);
+        let mut round = Round::new("crane", true);
+        round.submit("slate", &words).unwrap(); //a green, e green
+        assert_eq!(round.check("abbey", &words), Err(GuessError::Difficult));
+        assert_eq!(round.check("trace", &words), Ok(()));
+    }
+
+    #[test]
+    fn length_counts_graphemes() {
+        let alphabet: Vec<char> = "abcdefghijklmnñopqrstuvwxyz".chars().collect();
+        let words = WordSet::new(["cañas"], &alphabet);
+        let round = Round::with_alphabet("cañas", false, alphabet);
+        assert_eq!(round.check("cañas", &words), Ok(()));
+        //n + combining tilde is one letter, but not one of the alphabet
+        assert_eq!(
+            round.check("can\u{303}as", &words),
+            Err(GuessError::NotAlphabetic)
+        );
+        assert_eq!(round.check("cañass", &words), Err(GuessError::Length));
+    }
+
+    #[test]
+    fn candidates_follow_the_hints() {
+        let words = english(&["crane", "slate", "trace", "brace", "grace"]);
+        let mut round = Round::new("grace", false);
+        round.record("trace"); //t absent, race correct
+        let left: Vec<String> = round
+            .candidates(&words)
+            .iter()
+            .map(|x| words.unpack(x))
+            .collect();
+        assert_eq!(left, ["brace", "grace"]);
+    }
+
+    #[test]
+    fn finishes_on_win_or_out_of_turns() {
+        let mut round = Round::new("crane", false);
+        for _ in 0..MAX_TURNS - 1 {
+            round.record("slate");
+            assert!(!round.finished());
+        }
+        round.record("crane");
+        assert!(round.won() && round.finished());
+        let mut round = Round::new("crane", false);
+        for _ in 0..MAX_TURNS {
+            round.record("slate");
+        }
+        assert!(!round.won() && round.finished());
+    }
+
+    #[test]
+    fn describes_hints_in_words() {
+        let mut round = Round::new("crane", false);
+        round.record("react");
+        assert_eq!(
+            round.describe_guess(0),
+            "R present, E present, A correct, C present, T absent"
+        );
+        let alphabet = round.describe_alphabet();
+        assert!(alphabet.starts_with("Correct: A. Present: C E R. Absent: T. Not tried: B D F"));
+        assert!(alphabet.ends_with("X Y Z."));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::engine::{GuessError, Round, WORD_LEN, status_word, upper};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const PROTOCOL_VERSION: u32 = 1; //bump when a field changes meaning
+
+//how non-tty mood reports the game
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Format {
+    #[default]
+    Legacy, //GGRYR XXXG..., INVALID, CORRECT 3
+    Jsonl, //one Event per line
+}
+
+#[derive(Debug, Serialize)]
+pub struct Letter {
+    pub letter: char,
+    pub status: &'static str, //correct, present, absent
+}
+
+#[derive(Debug, Serialize)]
+pub struct WordCount {
+    pub word: String,
+    pub count: i32,
+}
+
+//one json object per line, tagged by "event"
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    GameStart {
+        protocol: u32,
+        game: usize, //1 for the first game of the session
+        mode: String,
+        player: Option<String>,
+        word_length: usize,
+        max_turns: usize,
+    },
+    Guess {
+        turn: usize,
+        word: String,
+        feedback: Vec<Letter>,
+        alphabet: BTreeMap<char, &'static str>, //every tried letter
+        remaining: usize,                       //turns left
+    },
+    Invalid {
+        word: String,
+        reason: &'static str, //length, not_alphabetic, not_in_list, difficult
+        message: &'static str,
+    },
+    GameEnd {
+        won: bool,
+        turns: usize,
+        answer: String,
+        #[serde(skip_serializing_if = "Option::is_none")] //to the last guess
+        seconds: Option<f64>,
+    },
+    Stats {
+        wins: i32,
+        losses: i32,
+        avg_guesses: f64, //over won games, 0 when none
+        top_guesses: Vec<WordCount>,
+        #[serde(skip_serializing_if = "Option::is_none")] //seconds, over timed wins
+        fastest_solve: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        average_time: Option<f64>,
+    },
+    Speedrun {
+        puzzles: usize,
+        solved: usize,
+        seconds: f64, //wall clock of the whole run
+    },
+    Survival {
+        length: usize, //words solved
+        score: usize,
+    },
+}
+
+impl Event {
+    pub fn game_start(
+        game: usize,
+        mode: String,
+        player: Option<String>,
+        max_turns: usize,
+    ) -> Event {
+        Event::GameStart {
+            protocol: PROTOCOL_VERSION,
+            game,
+            mode,
+            player,
+            word_length: WORD_LEN,
+            max_turns,
+        }
+    }
+
+    pub fn guess(round: &Round) -> Event {
+        //the last accepted guess of the round
+        let row = round.guesses.len() - 1;
+        let feedback = round.guesses[row]
+            .chars()
+            .zip(round.feedback[row].iter())
+            .map(|(c, &status)| Letter {
+                letter: upper(c),
+                status: status_word(status),
+            })
+            .collect();
+        let alphabet = round
+            .alphabet
+            .iter()
+            .filter(|&&c| round.letter_status(c) != 'X')
+            .map(|&c| (upper(c), status_word(round.letter_status(c))))
+            .collect();
+        Event::Guess {
+            turn: round.guesses.len(),
+            word: round.guesses[row].to_uppercase(),
+            feedback,
+            alphabet,
+            remaining: round.max_turns - round.guesses.len(),
+        }
+    }
+
+    pub fn invalid(word: &str, error: GuessError) -> Event {
+        Event::Invalid {
+            word: word.to_string(),
+            reason: error.code(),
+            message: error.message(),
+        }
+    }
+
+    pub fn emit(&self) {
+        println!("{}", serde_json::to_string(self).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::words::WordSet;
+    use serde_json::json;
+
+    fn value(event: &Event) -> serde_json::Value {
+        serde_json::to_value(event).unwrap()
+    }
+
+    #[test]
+    fn game_start_and_end() {
+        assert_eq!(
+            value(&Event::game_start(1, "daily".to_string(), None, 6)),
+            json!({
+                "event": "game_start",
+                "protocol": PROTOCOL_VERSION,
+                "game": 1,
+                "mode": "daily",
+                "player": null,
+                "word_length": 5,
+                "max_turns": 6
+            })
+        );
+        //untimed games leave seconds out
+        let end = Event::GameEnd {
+            won: true,
+            turns: 3,
+            answer: "CRANE".to_string(),
+            seconds: None,
+        };
+        assert_eq!(
+            value(&end),
+            json!({"event": "game_end", "won": true, "turns": 3, "answer": "CRANE"})
+        );
+    }
+
+    #[test]
+    fn guess_reports_feedback_and_tried_letters() {
+        let words = WordSet::new(["crane", "react"], &('a'..='z').collect::<Vec<char>>());
+        let mut round = Round::new("crane", false);
+        round.submit("react", &words).unwrap();
+        assert_eq!(
+            value(&Event::guess(&round)),
+            json!({
+                "event": "guess",
+                "turn": 1,
+                "word": "REACT",
+                "feedback": [
+                    {"letter": "R", "status": "present"},
+                    {"letter": "E", "status": "present"},
+                    {"letter": "A", "status": "correct"},
+                    {"letter": "C", "status": "present"},
+                    {"letter": "T", "status": "absent"}
+                ],
+                "alphabet": {"A": "correct", "C": "present", "E": "present", "R": "present", "T": "absent"},
+                "remaining": 5
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_carries_the_reason_code() {
+        assert_eq!(
+            value(&Event::invalid("zzzzz", GuessError::NotInList)),
+            json!({
+                "event": "invalid",
+                "word": "zzzzz",
+                "reason": "not_in_list",
+                "message": "Not in word list"
+            })
+        );
+    }
+}
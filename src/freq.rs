@@ -0,0 +1,138 @@
+use crate::lang::Language;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::path::Path;
+
+//how common words are: "word weight" per line, bigger is more common
+#[derive(Debug, Clone, Default)]
+pub struct Frequencies {
+    weights: HashMap<String, f64>,
+    floor: f64, //words missing from the table count as the rarest one in it
+}
+
+impl Frequencies {
+    pub fn load(
+        path: &Path,
+        language: &Language,
+    ) -> Result<Frequencies, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let mut weights = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let mut parts = line.split_whitespace();
+            let (Some(word), weight) = (parts.next(), parts.next()) else {
+                continue; //blank line
+            };
+            let weight: f64 = match weight.map(|x| x.parse()) {
+                Some(Ok(x)) if x >= 0.0 && f64::is_finite(x) => x,
+                _ => {
+                    return Err(format!(
+                        "{}:{}: expect a word and a weight of 0 or more",
+                        path.display(),
+                        i + 1
+                    )
+                    .into());
+                }
+            };
+            weights.insert(language.normalize(word), weight);
+        }
+        if weights.is_empty() {
+            return Err(format!("{}: no words", path.display()).into());
+        }
+        let floor = weights.values().copied().fold(f64::INFINITY, f64::min);
+        Ok(Frequencies { weights, floor })
+    }
+
+    pub fn weight(&self, word: &str) -> f64 {
+        self.weights.get(word).copied().unwrap_or(self.floor)
+    }
+
+    pub fn shuffle(&self, words: &mut [String], rng: &mut impl Rng) {
+        //weighted order without repeats: sort by u^(1/weight), common words tend to come first
+        let mut keyed: Vec<(f64, String)> = words
+            .iter()
+            .map(|x| (rng.r#gen::<f64>().powf(1.0 / self.weight(x)), x.clone()))
+            .collect();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (x, (_, word)) in words.iter_mut().zip(keyed) {
+            *x = word;
+        }
+    }
+}
+
+pub fn shuffle(words: &mut [String], frequencies: Option<&Frequencies>, rng: &mut impl Rng) {
+    //the answer order of a seed, weighted when there is a table
+    match frequencies {
+        Some(x) => x.shuffle(words, rng),
+        None => words.shuffle(rng),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn table(text: &str) -> Result<Frequencies, String> {
+        let path = crate::testutil::temp_file("freq.txt", text);
+        let result = Frequencies::load(&path, &Language::english());
+        std::fs::remove_file(path).unwrap();
+        result.map_err(|e| e.to_string())
+    }
+
+    fn words() -> Vec<String> {
+        ["crane", "slate", "abbey", "geese", "eerie", "trace"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn loads_weights() {
+        let freq = table("CRANE 50\n\nslate 0.5  \nabbey 2\n").unwrap();
+        assert_eq!(freq.weight("crane"), 50.0);
+        //missing words count as the rarest one
+        assert_eq!(freq.weight("geese"), 0.5);
+        assert!(
+            table("crane\n")
+                .unwrap_err()
+                .ends_with(":1: expect a word and a weight of 0 or more")
+        );
+        assert!(table("crane 1\nslate -2\n").unwrap_err().contains(":2:"));
+        assert!(table("crane NaN\n").is_err());
+        assert!(table("\n\n").unwrap_err().ends_with("no words"));
+    }
+
+    #[test]
+    fn shuffle_is_reproducible_from_the_seed() {
+        let freq = table("crane 100\nslate 1\n").unwrap();
+        let shuffled = |seed: u64| {
+            let mut list = words();
+            freq.shuffle(&mut list, &mut StdRng::seed_from_u64(seed));
+            list
+        };
+        assert_eq!(shuffled(42), shuffled(42));
+        let mut sorted = shuffled(42);
+        sorted.sort();
+        let mut all = words();
+        all.sort();
+        assert_eq!(sorted, all);
+        assert!((0..20).any(|x| shuffled(x) != shuffled(42)));
+    }
+
+    #[test]
+    fn common_words_come_first_more_often() {
+        let freq = table("crane 100\nslate 1\n").unwrap();
+        let first = (0..200)
+            .filter(|&seed| {
+                let mut list = words();
+                freq.shuffle(&mut list, &mut StdRng::seed_from_u64(seed));
+                list[0] == "crane"
+            })
+            .count();
+        //1 in 6 without weights, about 100 in 105 with them
+        assert!(first > 150, "{}", first);
+    }
+}
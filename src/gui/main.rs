@@ -1,362 +1,1437 @@
 use eframe::egui;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
 
+#[path = "../builtin_words.rs"]
 mod builtin_words;
-use builtin_words::{ACCEPTABLE, FINAL};
+#[path = "../engine.rs"]
+#[allow(dead_code)]
+mod engine;
+#[path = "../freq.rs"]
+mod freq;
+#[path = "../lang.rs"]
+mod lang;
+#[path = "../leaderboard.rs"]
+#[allow(dead_code)]
+mod leaderboard;
+#[path = "../race.rs"]
+#[allow(dead_code)]
+mod race;
+#[path = "../store.rs"]
+#[allow(dead_code)]
+mod store;
+#[cfg(test)]
+#[path = "../testutil.rs"]
+mod testutil;
+#[path = "../theme.rs"]
+#[allow(dead_code)]
+mod theme;
+#[path = "../tips.rs"]
+#[allow(dead_code)]
+mod tips;
+#[path = "../words.rs"]
+#[allow(dead_code)]
+mod words;
+use engine::{MAX_TURNS, Round, WORD_LEN, clock, upper};
+use store::{Game, StateStore};
+use words::WordSet;
 
 #[derive(Default)]
 struct WordleApp {
     answer: String,
-    guesses: Vec<String>,
-    feedback: Vec<Vec<char>>,
+    round: Round, //guesses, hints and keyboard state
     current_guess: String,
     game_over: bool,
     message: String,
-    keyboard_state: HashMap<char, char>,
-    accept_list: Vec<String>,
+    accept_list: WordSet,
     final_list: Vec<String>,
     config: GuiConfig,
-    green_list: Vec<(char, usize)>,
-    yellow_list: Vec<char>,
-    game_history: JsonState,
+    state_store: Option<Box<dyn StateStore>>,
     win_num: i32,
+    player: String,       //profile the games are recorded for
+    players: Vec<String>, //known profiles
+    new_player: String,   //name typed in the profile picker
+    board: LeaderboardPanel,
+    anim: Animations,
+    now: f64, //ctx time of the current frame, seconds
+    show_settings: bool,
+    settings_error: String,
+    games_started: usize, //rounds since the settings were applied, offsets -d
+    played: Vec<String>,  //answers of the player's cycle, new_game skips them
+    hints: Hints,
+    hints_outdated: bool, //round changed since the last ranking
+    stats: StatsPanel,
+    palette: theme::Palette,                //resolved from config.theme
+    language: lang::Language,               //loaded from config.lang
+    frequencies: Option<freq::Frequencies>, //loaded from config.freq
+    seed: u64, //config.seed or drawn by init, orders the answers of the session
+    race: RacePanel,
+    started: f64,       //ctx time the current game began
+    ended: Option<f64>, //seconds from started to the end, shown once the game is over
+    times: Vec<u64>,    //ms from started to each guess
 }
 
+//joined race room, the host scores guesses and keeps the results
+struct RacePanel {
+    open: bool,
+    address: String,
+    client: Option<race::RaceClient>,
+    view: race::RaceView,
+    in_race: bool, //the board shows the race instead of a local game
+}
+
+impl Default for RacePanel {
+    fn default() -> RacePanel {
+        RacePanel {
+            open: false,
+            address: "ws://127.0.0.1:8090/room".to_string(),
+            client: None,
+            view: race::RaceView::default(),
+            in_race: false,
+        }
+    }
+}
+
+const HINT_COUNT: usize = 10;
+
+//entropy suggestions, computed on a worker thread
+type Ranking = (u64, Vec<(words::Packed, f64)>); //job generation, best words first
+
 #[derive(Default)]
+struct Hints {
+    candidates: usize, //possible answers left
+    ranking: Vec<(String, f64)>,
+    pending: Option<mpsc::Receiver<Ranking>>, //dropped when a newer job starts
+    generation: u64,                          //of the newest job, results of older ones are dropped
+    cancel: Arc<AtomicBool>,                  //stops the running job
+}
+
+const FLIP_TIME: f64 = 0.35; //one tile turning over
+const FLIP_STAGGER: f64 = 0.25; //delay between neighbouring tiles
+const SHAKE_TIME: f64 = 0.4;
+const BOUNCE_TIME: f64 = 0.5;
+const BOUNCE_STAGGER: f64 = 0.1;
+
+//start times of the running animations, None when idle
+#[derive(Default)]
+struct Animations {
+    reveal: Option<(usize, f64)>, //row being flipped
+    shake: Option<f64>,           //current input row, invalid word
+    bounce: Option<(usize, f64)>, //winning row
+}
+
+impl Animations {
+    fn running(&self, now: f64) -> bool {
+        let reveal_end = FLIP_STAGGER * (WORD_LEN - 1) as f64 + FLIP_TIME;
+        let bounce_end = BOUNCE_STAGGER * (WORD_LEN - 1) as f64 + BOUNCE_TIME;
+        self.reveal.is_some_and(|(_, t)| now - t < reveal_end)
+            || self.shake.is_some_and(|t| now - t < SHAKE_TIME)
+            || self.bounce.is_some_and(|(_, t)| now - t < bounce_end)
+    }
+
+    fn flip(&self, row: usize, col: usize, now: f64) -> Option<f64> {
+        //progress 0..1 of a tile in the revealing row, None: not started
+        let (r, start) = self.reveal?;
+        if r != row {
+            return Some(1.0);
+        }
+        let t = (now - start - col as f64 * FLIP_STAGGER) / FLIP_TIME;
+        if t < 0.0 { None } else { Some(t.min(1.0)) }
+    }
+
+    fn shake_offset(&self, now: f64, cell: f32) -> f32 {
+        match self.shake {
+            Some(start) if now - start < SHAKE_TIME => {
+                let t = (now - start) / SHAKE_TIME;
+                ((t * 40.0).sin() * (1.0 - t)) as f32 * cell * 0.16
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn bounce_offset(&self, row: usize, col: usize, now: f64, cell: f32) -> f32 {
+        match self.bounce {
+            Some((r, start)) if r == row => {
+                let t = (now - start - col as f64 * BOUNCE_STAGGER) / BOUNCE_TIME;
+                if (0.0..1.0).contains(&t) {
+                    -((t * std::f64::consts::PI).sin()) as f32 * cell * 0.32
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct LeaderboardPanel {
+    open: bool,
+    metric: leaderboard::Metric,
+    mode: Option<String>,
+    from: String, //YYYY-MM-DD, empty: no limit
+    to: String,
+    entries: Vec<leaderboard::Entry>,
+    runs: Vec<leaderboard::RunEntry>, //survival ranks best runs instead
+    error: String,
+}
+
+#[derive(Default)]
+struct StatsPanel {
+    open: bool,
+    stats: store::Stats,
+    games: Vec<Game>,               //the player's history, newest first
+    distribution: [i32; MAX_TURNS], //won games by number of guesses
+    replay: Option<usize>,          //index into games
+}
+
+//sizes derived from the space the central panel gets
+struct Layout {
+    cell: f32, //grid tile edge
+    gap: f32,
+    key: egui::Vec2, //letter key
+    key_gap: f32,
+    spacing: f32, //between title, grid, keyboard and message
+}
+
+impl Layout {
+    fn new(available: egui::Vec2, cols: usize, rows: usize) -> Layout {
+        let key_gap = (available.x / 120.0).clamp(3.0, 10.0);
+        let key_w = ((available.x * 0.95 - 9.0 * key_gap) / 10.0).clamp(18.0, 64.0); //10 keys on the top row
+        let key_h = (key_w * 1.3).min(available.y / 14.0).max(24.0);
+        let spacing = (available.y / 60.0).clamp(6.0, 30.0);
+        let title = 44.0;
+        let message = 36.0;
+        let keyboard = 4.0 * (key_h + key_gap); //3 letter rows and the action row
+        let grid_h = (available.y - title - message - keyboard - 4.0 * spacing).max(60.0);
+        let grid_w = available.x * 0.9;
+        //tiles keep a gap of 12% of their edge
+        let cell = (grid_w / (cols as f32 * 1.12 - 0.12))
+            .min(grid_h / (rows as f32 * 1.12 - 0.12))
+            .clamp(12.0, 96.0);
+        Layout {
+            cell,
+            gap: cell * 0.12,
+            key: egui::vec2(key_w, key_h),
+            key_gap,
+            spacing,
+        }
+    }
+}
+
+//same options as the command line, kept in eframe storage between runs
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 struct GuiConfig {
     difficult: bool,
     seed: Option<u64>,
+    day: usize, //like -d, first game of a seeded run
+    final_set: Option<PathBuf>,
+    acceptable_set: Option<PathBuf>,
+    state: PathBuf,
+    tips: bool,
+    theme: String,                            //builtin name or a key of themes
+    themes: BTreeMap<String, theme::Palette>, //custom palettes, same format as the cli config
+    reduce_motion: bool,                      //no animations
+    player: Option<String>,                   //last used profile
+    time_limit: Option<u64>,                  //seconds per game, like --time-limit
+    custom_lists: bool,                       //like --custom-lists
+    lang: Option<PathBuf>,                    //language pack, like --lang
+    freq: Option<PathBuf>,                    //word frequency table, like --freq
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
-struct JsonState {
-    #[serde(default)] //default: allow {} empty json file
-    total_rounds: i32,
-    #[serde(default)]
-    games: Vec<Game>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Default)]
-struct Game {
-    answer: String,
-    guesses: Vec<String>,
+impl Default for GuiConfig {
+    fn default() -> GuiConfig {
+        GuiConfig {
+            difficult: false,
+            seed: None,
+            day: 1,
+            final_set: None,
+            acceptable_set: None,
+            state: PathBuf::from("input.json"),
+            tips: false,
+            theme: "dark".to_string(),
+            themes: BTreeMap::new(),
+            reduce_motion: false,
+            player: None,
+            time_limit: None,
+            custom_lists: false,
+            lang: None,
+            freq: None,
+        }
+    }
 }
 
 impl WordleApp {
     fn init(&mut self) {
-        self.final_list = FINAL.iter().map(|s| s.to_string()).collect();
-        self.accept_list = ACCEPTABLE.iter().map(|s| s.to_string()).collect();
-        self.config.difficult = false;
-        match self.load_state_json(&PathBuf::from("input.json")) {
-            Result::Ok(x) => {
-                self.game_history = x;
-                for iter in self.game_history.games.iter() {
-                    if iter.guesses[iter.guesses.len() - 1] == iter.answer {
-                        self.win_num += 1;
-                    }
+        //(re)load word lists and state file from the settings
+        self.settings_error.clear();
+        self.apply_theme();
+        self.language = match self.config.lang {
+            Some(ref x) => lang::Language::load(x).unwrap_or_else(|e| {
+                self.settings_error = format!("language: {}", e);
+                lang::Language::english()
+            }),
+            None => lang::Language::english(),
+        };
+        (self.final_list, self.accept_list) = words::load_lists(
+            self.config.final_set.as_deref(),
+            self.config.acceptable_set.as_deref(),
+            self.config.custom_lists,
+            &self.language,
+        )
+        .unwrap_or_else(|e| {
+            self.settings_error = format!("word lists: {}", e);
+            self.language = lang::Language::english();
+            words::load_lists(None, None, false, &self.language).unwrap()
+        });
+        self.frequencies = self.config.freq.as_ref().and_then(|x| {
+            freq::Frequencies::load(x, &self.language)
+                .map_err(|e| self.settings_error = format!("frequencies: {}", e))
+                .ok()
+        });
+        //same order as the command line with -s
+        self.seed = match self.config.seed {
+            Some(x) => x,
+            None => rand::thread_rng().r#gen(),
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(self.seed);
+        freq::shuffle(&mut self.final_list, self.frequencies.as_ref(), &mut rng);
+        self.games_started = 0;
+
+        let path = self.config.state.clone();
+        //only a missing file is started empty, a broken one is left alone
+        let opened = if path.exists() || store::is_sqlite_path(&path) {
+            store::open_store(&path)
+        } else {
+            store::JsonStore::create(&path).map(|x| Box::new(x) as Box<dyn StateStore>)
+        };
+        self.state_store = opened
+            .map_err(|e| self.settings_error = format!("state file {}: {}", path.display(), e))
+            .ok();
+        self.players.clear();
+        if let Some(ref x) = self.state_store {
+            self.players = x.players().unwrap_or_default();
+        }
+        let player = self
+            .config
+            .player
+            .clone()
+            .or_else(|| self.players.first().cloned())
+            .unwrap_or_else(|| "Player".to_string());
+        self.switch_player(player);
+    }
+
+    fn apply_theme(&mut self) {
+        self.palette =
+            theme::resolve(&self.config.theme, &self.config.themes).unwrap_or_else(|e| {
+                self.settings_error = e;
+                theme::Palette::dark()
+            });
+    }
+
+    fn load_palettes(&mut self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        //"themes" of a cli config file
+        #[derive(Deserialize)]
+        struct ThemeFile {
+            #[serde(default)]
+            themes: BTreeMap<String, theme::Palette>,
+        }
+        let file: ThemeFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if file.themes.is_empty() {
+            return Err("no themes in the file".into());
+        }
+        self.config.themes.extend(file.themes);
+        Ok(())
+    }
+
+    fn switch_player(&mut self, name: String) {
+        //reload the record of the chosen profile and start over
+        if !self.players.contains(&name) {
+            self.players.push(name.clone());
+            self.players.sort();
+        }
+        self.player = name;
+        self.config.player = Some(self.player.clone());
+        self.win_num = match self.state_store {
+            Some(ref x) => x.stats(Some(&self.player)).map(|s| s.success).unwrap_or(0),
+            None => 0,
+        };
+        self.refresh_stats();
+        self.played = match self.state_store {
+            Some(ref x) => x
+                .past_answers(Some(&self.player))
+                .map(|past| store::cycle_answers(&past, &self.final_list))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        self.new_game();
+    }
+
+    fn record_game(&mut self) {
+        //every local game ends here, the clock stops
+        self.ended.get_or_insert(self.now - self.started);
+        let game = Game {
+            answer: self.answer.clone(),
+            guesses: self
+                .round
+                .guesses
+                .iter()
+                .map(|x| x.to_uppercase())
+                .collect(),
+            player: Some(self.player.clone()),
+            played_at: Some(store::now()),
+            mode: Some(
+                //like game_mode of the command line
+                if self.config.difficult {
+                    "difficult"
+                } else if self.config.seed.is_some() && self.config.day != 1 {
+                    "daily"
+                } else {
+                    "normal"
                 }
-            }
-            Err(_) => {
-                self.game_history = JsonState {
-                    total_rounds: 0,
-                    games: Vec::new(),
-                };
-                self.win_num = 0;
+                .to_string(),
+            ),
+            opponent: None,
+            times: self.times.clone(),
+        };
+        if let Some(ref mut x) = self.state_store {
+            let _ = x.record_game(&game);
+        }
+        self.refresh_leaderboard();
+        self.refresh_stats();
+    }
+
+    fn refresh_stats(&mut self) {
+        let Some(ref x) = self.state_store else {
+            return;
+        };
+        self.stats.stats = x.stats(Some(&self.player)).unwrap_or_default();
+        self.stats.games = x
+            .load()
+            .map(|json_data| json_data.games)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|game| game.player.as_deref() == Some(self.player.as_str()))
+            .rev()
+            .collect();
+        self.stats.distribution = [0; MAX_TURNS];
+        for game in self.stats.games.iter().filter(|x| x.won()) {
+            if (1..=MAX_TURNS).contains(&game.guesses.len()) {
+                self.stats.distribution[game.guesses.len() - 1] += 1;
             }
         }
-        self.new_game();
+        self.stats.replay = None;
     }
 
-    fn load_state_json(&mut self, path: &PathBuf) -> Result<JsonState, Box<dyn std::error::Error>> {
-        //load state json and return Result
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let u = serde_json::from_reader(reader)?;
-        Ok(u)
+    fn render_stats(&mut self, ctx: &egui::Context) {
+        let mut open = self.stats.open;
+        egui::Window::new("Statistics")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let stats = &self.stats.stats;
+                let win_rate = if stats.total_rounds > 0 {
+                    stats.success as f64 * 100.0 / stats.total_rounds as f64
+                } else {
+                    0.0
+                };
+                ui.horizontal(|ui| {
+                    for (value, title) in [
+                        (stats.total_rounds.to_string(), "Played"),
+                        (format!("{:.0}", win_rate), "Win %"),
+                        (stats.current_streak.to_string(), "Current Streak"),
+                        (stats.max_streak.to_string(), "Max Streak"),
+                        (
+                            stats
+                                .fastest_solve
+                                .map_or("-".to_string(), |x| clock(x as u64)),
+                            "Fastest",
+                        ),
+                        (
+                            stats
+                                .average_time
+                                .map_or("-".to_string(), |x| clock(x as u64)),
+                            "Avg Time",
+                        ),
+                    ] {
+                        ui.vertical(|ui| {
+                            ui.heading(value);
+                            ui.label(title);
+                        });
+                        ui.add_space(12.0);
+                    }
+                });
+                ui.separator();
+                ui.strong("Guess Distribution");
+                self.render_distribution(ui);
+                ui.separator();
+                ui.strong("History");
+                self.render_history(ui);
+                if let Some(game) = self.stats.replay.and_then(|i| self.stats.games.get(i)) {
+                    ui.separator();
+                    ui.strong(format!("Replay: {}", game.answer.to_uppercase()));
+                    paint_board(ui, &replay_round(game), &self.palette, 28.0);
+                }
+            });
+        self.stats.open = open;
     }
 
-    fn write_state_json(
-        &self,
-        path: &PathBuf,
-        json_data: &JsonState,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let file = File::create(path)?;
-        serde_json::to_writer_pretty(file, &json_data)?;
-        Ok(())
+    fn render_distribution(&self, ui: &mut egui::Ui) {
+        //horizontal bars scaled to the most common count
+        let most = self
+            .stats
+            .distribution
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let last = self.round.won().then_some(self.round.guesses.len());
+        let width = ui.available_width().clamp(200.0, 360.0) - 24.0;
+        for (i, &count) in self.stats.distribution.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}", i + 1));
+                let bar = (width * count as f32 / most as f32).max(24.0);
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(bar, 18.0), egui::Sense::hover());
+                let color = if self.game_over && last == Some(i + 1) {
+                    color32(self.palette.correct) //the game just won
+                } else {
+                    color32(self.palette.absent)
+                };
+                ui.painter().rect_filled(rect, 2.0, color);
+                ui.painter().text(
+                    rect.right_center() - egui::vec2(4.0, 0.0),
+                    egui::Align2::RIGHT_CENTER,
+                    count.to_string(),
+                    egui::FontId::proportional(13.0),
+                    color32(self.palette.text),
+                );
+            });
+        }
     }
 
-    fn new_game(&mut self) {
-        use rand::Rng;
+    fn render_history(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical()
+            .max_height(180.0)
+            .show(ui, |ui| {
+                egui::Grid::new("history_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for title in ["Date", "Answer", "Result", "Mode"] {
+                            ui.strong(title);
+                        }
+                        ui.end_row();
+                        for (i, game) in self.stats.games.iter().enumerate() {
+                            let date = game.played_at.map(format_date).unwrap_or_default();
+                            let selected = self.stats.replay == Some(i);
+                            //click a game to see its board
+                            if ui.selectable_label(selected, date).clicked()
+                                | ui.selectable_label(selected, game.answer.to_uppercase())
+                                    .clicked()
+                            {
+                                self.stats.replay = if selected { None } else { Some(i) };
+                            }
+                            ui.label(if game.won() {
+                                format!("{}/{}", game.guesses.len(), MAX_TURNS)
+                            } else {
+                                format!("X/{}", MAX_TURNS)
+                            });
+                            ui.label(game.mode.as_deref().unwrap_or("normal"));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
 
-        let mut rng = if let Some(seed) = self.config.seed {
-            rand::rngs::StdRng::seed_from_u64(seed)
-        } else {
-            rand::rngs::StdRng::from_entropy()
+    fn refresh_leaderboard(&mut self) {
+        let parse = |date: &str| {
+            if date.trim().is_empty() {
+                Ok(None)
+            } else {
+                leaderboard::parse_date(date).map(Some)
+            }
+        };
+        let (from, to) = match (parse(&self.board.from), parse(&self.board.to)) {
+            (Ok(from), Ok(to)) => (from, to.map(|x| x + 86400)),
+            _ => {
+                self.board.error = "Dates must look like 2024-01-31".to_string();
+                return;
+            }
         };
+        self.board.error.clear();
+        let filter = leaderboard::Filter {
+            from,
+            to,
+            mode: self.board.mode.clone(),
+        };
+        if let Some(ref x) = self.state_store
+            && let Ok(json_data) = x.load()
+        {
+            if filter.mode.as_deref() == Some("survival") {
+                self.board.runs = leaderboard::rank_runs(&json_data, &filter);
+            } else {
+                self.board.entries = leaderboard::rank(&json_data, &filter, self.board.metric);
+            }
+        }
+    }
 
-        let index = rng.gen_range(0..self.final_list.len());
-        self.answer = self.final_list[index].clone().to_uppercase();
-        self.game_history.games.push(Game {
-            answer: self.answer.clone(),
-            guesses: Vec::new(),
-        });
+    fn render_leaderboard(&mut self, ctx: &egui::Context) {
+        let mut open = self.board.open;
+        let mut changed = false;
+        egui::Window::new("Leaderboard")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Metric")
+                        .selected_text(format!("{:?}", self.board.metric))
+                        .show_ui(ui, |ui| {
+                            use leaderboard::Metric;
+                            for metric in [
+                                Metric::Score,
+                                Metric::WinRate,
+                                Metric::AvgGuesses,
+                                Metric::Streak,
+                            ] {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut self.board.metric,
+                                        metric,
+                                        format!("{:?}", metric),
+                                    )
+                                    .changed();
+                            }
+                        });
+                    egui::ComboBox::from_label("Mode")
+                        .selected_text(self.board.mode.as_deref().unwrap_or("all"))
+                        .show_ui(ui, |ui| {
+                            changed |= ui
+                                .selectable_value(&mut self.board.mode, None, "all")
+                                .changed();
+                            for mode in [
+                                "normal",
+                                "difficult",
+                                "daily",
+                                "race",
+                                "duel",
+                                "speedrun",
+                                "survival",
+                            ] {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut self.board.mode,
+                                        Some(mode.to_string()),
+                                        mode,
+                                    )
+                                    .changed();
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("From");
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut self.board.from).desired_width(90.0))
+                        .lost_focus();
+                    ui.label("To");
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut self.board.to).desired_width(90.0))
+                        .lost_focus();
+                });
+                if !self.board.error.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.board.error);
+                }
+                ui.separator();
+                if self.board.mode.as_deref() == Some("survival") {
+                    egui::Grid::new("runs_grid").striped(true).show(ui, |ui| {
+                        for title in ["#", "Player", "Runs", "Length", "Score"] {
+                            ui.strong(title);
+                        }
+                        ui.end_row();
+                        for x in self.board.runs.iter() {
+                            ui.label(x.rank.to_string());
+                            ui.label(&x.player);
+                            ui.label(x.runs.to_string());
+                            ui.label(x.best_length.to_string());
+                            ui.label(x.best_score.to_string());
+                            ui.end_row();
+                        }
+                    });
+                    return;
+                }
+                egui::Grid::new("leaderboard_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for title in ["#", "Player", "Games", "Win %", "Avg", "Streak", "Score"] {
+                            ui.strong(title);
+                        }
+                        ui.end_row();
+                        for x in self.board.entries.iter() {
+                            ui.label(x.rank.to_string());
+                            ui.label(&x.player);
+                            ui.label(x.games.to_string());
+                            ui.label(format!("{:.1}", x.win_rate * 100.0));
+                            ui.label(format!("{:.2}", x.avg_guesses));
+                            ui.label(x.max_streak.to_string());
+                            ui.label(format!("{:.1}", x.score));
+                            ui.end_row();
+                        }
+                    });
+            });
+        self.board.open = open;
+        if changed {
+            self.refresh_leaderboard();
+        }
+    }
+
+    fn new_game(&mut self) {
+        //walk the list shuffled by the session seed, from -d when the seed is fixed,
+        //past the answers of this cycle like the command line
+        let first = match self.config.seed {
+            Some(_) => self.config.day.max(1) - 1,
+            None => 0,
+        };
+        let len = self.final_list.len();
+        let id = first + self.games_started;
+        let answer = match (0..len)
+            .map(|i| &self.final_list[(id + i) % len])
+            .find(|x| !self.played.contains(x))
+        {
+            Some(x) => x.clone(),
+            None => {
+                self.played.clear();
+                self.final_list[id % len].clone()
+            }
+        };
+        self.games_started += 1;
+        self.answer = answer.to_uppercase();
+        self.played.push(answer);
 
-        self.guesses.clear();
-        self.feedback.clear();
+        self.round =
+            Round::with_alphabet(&self.answer, self.config.difficult, self.language.letters());
+        self.anim = Animations::default();
         self.current_guess.clear();
         self.game_over = false;
         self.message.clear();
-        self.keyboard_state.clear();
-        self.green_list.clear();
-        self.yellow_list.clear();
+        self.hints_outdated = true;
+        self.started = self.now;
+        self.ended = None;
+        self.times.clear();
     }
 
-    fn submit_guess(&mut self) {
-        if self.current_guess.len() != 5 {
-            self.message = "Word must be 5 letters".to_string();
-            return;
+    fn check_clock(&mut self, ctx: &egui::Context) -> Option<String> {
+        //the timer above the grid, ends the game when the time limit is hit
+        if self.race.in_race {
+            return None;
+        }
+        let elapsed = match self.ended {
+            Some(x) if self.game_over => x,
+            //a race board kept after the race
+            _ if self.game_over => self.times.last().map_or(0.0, |&x| x as f64 / 1000.0),
+            _ => self.now - self.started,
+        };
+        if let Some(limit) = self.config.time_limit
+            && !self.game_over
+            && elapsed >= limit as f64
+        {
+            self.game_over = true;
+            self.ended = Some(limit as f64);
+            self.message = format!("Time's up! The word was {}", self.answer);
+            self.record_game();
+            return Some(clock(limit));
+        }
+        if self.game_over {
+            return Some(clock(elapsed as u64));
         }
+        //wake up when the shown second changes, nothing in between
+        let shown = match self.config.time_limit {
+            Some(limit) => limit as f64 - elapsed,
+            None => elapsed,
+        };
+        let tick = match shown.fract() {
+            x if x > 0.0 && self.config.time_limit.is_some() => x,
+            x => 1.0 - x,
+        };
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(tick));
+        Some(match self.config.time_limit {
+            Some(_) => format!("{} left", clock(shown.ceil() as u64)),
+            None => clock(elapsed as u64),
+        })
+    }
 
-        let guess_lower = self.current_guess.to_lowercase();
-        if !self.accept_list.contains(&guess_lower) {
-            self.message = "Not in word list".to_string();
+    fn update_candidates(&mut self, ctx: &egui::Context) {
+        //start ranking the words still possible, the frame loop keeps going
+        if !self.config.tips {
             return;
         }
+        let candidates = self.round.candidates(&self.accept_list);
+        let prior: Option<Vec<f64>> = self.frequencies.as_ref().map(|f| {
+            candidates
+                .iter()
+                .map(|x| f.weight(&self.accept_list.unpack(x)))
+                .collect()
+        });
+        self.hints.candidates = candidates.len();
+        self.hints.ranking.clear();
+        //the previous job is outdated, stop it instead of letting it finish
+        self.hints.cancel.store(true, Ordering::Relaxed);
+        self.hints.cancel = Arc::new(AtomicBool::new(false));
+        self.hints.generation += 1;
+        let (sender, receiver) = mpsc::channel();
+        self.hints.pending = Some(receiver);
+        let ctx = ctx.clone();
+        let generation = self.hints.generation;
+        let cancel = self.hints.cancel.clone();
+        std::thread::spawn(move || {
+            let Some(mut ranking) =
+                tips::entropy_ranking_until(&candidates, prior.as_deref(), &cancel)
+            else {
+                return;
+            };
+            ranking.truncate(HINT_COUNT);
+            if sender.send((generation, ranking)).is_ok() {
+                ctx.request_repaint(); //wake the ui to show it
+            }
+        });
+    }
 
-        if self.config.difficult && !self.examine(&self.current_guess) {
-            self.message = "Difficult Mode: against the rule".to_string();
+    fn render_hints(&mut self, ui: &mut egui::Ui) {
+        if let Some(ref receiver) = self.hints.pending
+            && let Ok((generation, ranking)) = receiver.try_recv()
+            && generation == self.hints.generation
+        {
+            self.hints.ranking = ranking
+                .iter()
+                .map(|(word, entropy)| (self.accept_list.unpack(word), *entropy))
+                .collect();
+            self.hints.pending = None;
+        }
+        ui.heading("Hints");
+        ui.separator();
+        ui.label(format!("{} possible words left", self.hints.candidates));
+        ui.add_space(8.0);
+        if self.hints.pending.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Ranking...");
+            });
             return;
         }
+        egui::Grid::new("hints_grid").striped(true).show(ui, |ui| {
+            ui.strong("Word");
+            ui.strong("Entropy");
+            ui.end_row();
+            for (word, entropy) in self.hints.ranking.iter() {
+                //click to type the suggestion
+                if ui.link(word.to_uppercase()).clicked() && !self.game_over {
+                    self.current_guess = word.to_uppercase();
+                    self.message.clear();
+                }
+                ui.label(format!("{:.4}", entropy));
+                ui.end_row();
+            }
+        });
+    }
 
-        let current_guess_clone = self.current_guess.clone();
-        let feedback = self.calculate_feedback(&current_guess_clone);
-        let current_guess_clone = self.current_guess.clone();
-        self.guesses.push(current_guess_clone.clone());
-        self.feedback.push(feedback.clone());
-        self.update_keyboard_state(&current_guess_clone, &feedback);
+    fn type_text(&mut self, text: &str) {
+        //folded like the command line, a combining accent joins the letter before it
+        let typed = self
+            .language
+            .normalize(&format!("{}{}", self.current_guess, text));
+        if typed.chars().count() <= WORD_LEN && typed.chars().all(|c| self.language.is_letter(c)) {
+            self.current_guess = typed.chars().map(upper).collect();
+        }
+    }
 
-        self.current_guess.clear();
-        if let Some(current_game) = self
-            .game_history
-            .games
-            .iter_mut()
-            .nth((self.game_history.total_rounds) as usize)
-        {
-            current_game.guesses.push(current_guess_clone.clone());
+    fn submit_guess(&mut self) {
+        let guess_lower = self.current_guess.to_lowercase();
+        if self.race.in_race {
+            //the host answers with feedback or invalid
+            self.current_guess.clear();
+            self.race_send(race::ClientMessage::Guess { word: guess_lower });
+            return;
+        }
+        self.round.difficult = self.config.difficult; //mode can be switched mid game
+        let feedback = match self.round.submit(&guess_lower, &self.accept_list) {
+            Ok(x) => x,
+            Err(e) => {
+                self.message = e.message().to_string();
+                if !self.config.reduce_motion {
+                    self.anim.shake = Some(self.now);
+                }
+                return;
+            }
+        };
+        let row = self.round.guesses.len() - 1;
+        self.times.push(((self.now - self.started) * 1000.0) as u64);
+        self.hints_outdated = true;
+        if !self.config.reduce_motion {
+            self.anim.reveal = Some((row, self.now));
         }
+
+        self.current_guess.clear();
         if feedback.iter().all(|&c| c == 'G') {
             self.game_over = true;
-            self.game_history.total_rounds += 1;
             self.win_num += 1;
+            if !self.config.reduce_motion {
+                //jump once every tile has turned
+                let reveal_end = FLIP_STAGGER * (WORD_LEN - 1) as f64 + FLIP_TIME;
+                self.anim.bounce = Some((row, self.now + reveal_end));
+            }
             self.message = format!(
                 "You won in {} tries! Total success :{}",
-                self.guesses.len(),
+                self.round.guesses.len(),
                 self.win_num
             );
-
-            let _ = self.write_state_json(&PathBuf::from("input.json"), &self.game_history);
-        } else if self.guesses.len() >= 6 {
+            self.record_game();
+        } else if self.round.guesses.len() >= MAX_TURNS {
             self.game_over = true;
-            self.game_history.total_rounds += 1;
             self.message = format!(
                 "Game over! The word was {},Total success :{}",
                 self.answer, self.win_num
             );
-            let _ = self.write_state_json(&PathBuf::from("input.json"), &self.game_history);
+            self.record_game();
         }
     }
 
-    fn examine(&self, guess: &str) -> bool {
-        let guess_word_vector: Vec<char> = guess.chars().collect();
-        for iter in self.green_list.iter() {
-            if guess_word_vector[iter.1] != iter.0 {
-                return false;
-            }
+    fn race_send(&mut self, message: race::ClientMessage) {
+        if let Some(ref mut client) = self.race.client
+            && let Err(e) = client.send(&message)
+        {
+            self.message = e;
+            self.leave_race();
         }
-        for iter in self.yellow_list.iter() {
-            if !guess.contains(*iter) {
-                return false;
-            }
+    }
+
+    fn leave_race(&mut self) {
+        self.race.client = None;
+        if self.race.in_race {
+            self.race.in_race = false;
+            self.new_game();
         }
-        return true;
     }
 
-    fn calculate_feedback(&mut self, guess: &str) -> Vec<char> {
-        let mut feedback = vec!['R'; 5];
-        let mut answer_chars: Vec<char> = self.answer.chars().collect();
-        let guess_chars: Vec<char> = guess.chars().collect();
-        for i in 0..5 {
-            if guess_chars[i] == answer_chars[i] {
-                feedback[i] = 'G';
-                self.green_list.push((guess_chars[i], i));
-                answer_chars[i] = ' ';
+    fn poll_race(&mut self, ctx: &egui::Context) {
+        let Some(ref mut client) = self.race.client else {
+            return;
+        };
+        ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        let messages = match client.poll() {
+            Ok(x) => x,
+            Err(e) => {
+                self.message = e;
+                self.leave_race();
+                return;
+            }
+        };
+        for message in messages {
+            match message {
+                race::ServerMessage::Start { .. } => {
+                    self.race.in_race = true;
+                    self.anim = Animations::default();
+                    self.current_guess.clear();
+                    self.game_over = false;
+                }
+                race::ServerMessage::Feedback { .. } if !self.config.reduce_motion => {
+                    self.anim.reveal = Some((self.race.view.round.guesses.len(), self.now));
+                }
+                race::ServerMessage::Invalid { .. } if !self.config.reduce_motion => {
+                    self.anim.shake = Some(self.now);
+                }
+                _ => {}
+            }
+            let over = matches!(message, race::ServerMessage::Result { .. });
+            self.race.view.handle(message, &self.player);
+            self.message = self.race.view.message.clone();
+            if self.race.in_race {
+                self.round = self.race.view.round.clone();
+                self.game_over = self.round.finished();
+                self.hints_outdated = true;
+            }
+            if over {
+                //keep the final board, New Game is local again
+                self.race.in_race = false;
+                self.game_over = true;
             }
         }
-        for i in 0..5 {
-            if feedback[i] == 'R' {
-                if let Some(pos) = answer_chars.iter().position(|&c| c == guess_chars[i]) {
-                    feedback[i] = 'Y';
-                    self.yellow_list.push(guess_chars[i]);
-                    answer_chars[pos] = ' ';
+    }
+
+    fn render_race(&mut self, ui: &mut egui::Ui) {
+        if self.race.client.is_none() {
+            ui.label("Room address");
+            ui.text_edit_singleline(&mut self.race.address);
+            if ui.button("Join").clicked() {
+                match race::RaceClient::connect(self.race.address.trim(), &self.player) {
+                    Ok(x) => {
+                        self.race.client = Some(x);
+                        self.race.view = race::RaceView::default();
+                        self.message.clear();
+                    }
+                    Err(e) => self.message = format!("cannot join: {}", e),
                 }
             }
+            return;
+        }
+        let view = &self.race.view;
+        ui.strong(format!("Room {}", view.room));
+        ui.label(format!("Players: {}", view.players.join(", ")));
+        //opponents: colors only
+        for (name, rows) in view.opponents.iter() {
+            ui.separator();
+            ui.label(name);
+            for row in rows.iter() {
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 2.0;
+                    for &status in row.iter() {
+                        let (rect, response) =
+                            ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                        ui.painter()
+                            .rect_filled(rect, 2.0, color32(self.palette.color(status)));
+                        response.widget_info(|| {
+                            egui::WidgetInfo::labeled(
+                                egui::WidgetType::Label,
+                                engine::status_word(status),
+                            )
+                        });
+                    }
+                });
+            }
+        }
+        if !view.standings.is_empty() {
+            ui.separator();
+            for (i, x) in view.standings.iter().enumerate() {
+                let turns = if x.won {
+                    format!("{}/{}", x.turns, MAX_TURNS)
+                } else {
+                    format!("X/{}", MAX_TURNS)
+                };
+                ui.label(format!("{}. {} {}", i + 1, x.player, turns));
+            }
         }
+        ui.separator();
+        let racing = view.racing;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!racing, egui::Button::new("Start race"))
+                .clicked()
+            {
+                self.race_send(race::ClientMessage::Start);
+            }
+            if ui.button("Leave").clicked() {
+                self.leave_race();
+            }
+        });
+    }
 
-        feedback
+    fn render_profile_picker(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Player:");
+            let mut chosen = self.player.clone();
+            egui::ComboBox::from_id_source("player_picker")
+                .selected_text(&chosen)
+                .show_ui(ui, |ui| {
+                    for name in self.players.iter() {
+                        ui.selectable_value(&mut chosen, name.clone(), name);
+                    }
+                });
+            if chosen != self.player {
+                self.switch_player(chosen);
+            }
+            ui.text_edit_singleline(&mut self.new_player);
+            if ui.button("Add").clicked() && !self.new_player.trim().is_empty() {
+                let name = self.new_player.trim().to_string();
+                self.new_player.clear();
+                self.switch_player(name);
+            }
+            if ui.selectable_label(self.config.tips, "Hints").clicked() {
+                self.config.tips = !self.config.tips;
+                self.hints_outdated = true;
+            }
+            if ui.button("Settings").clicked() {
+                self.show_settings = !self.show_settings;
+            }
+            if ui.button("Leaderboard").clicked() {
+                self.board.open = !self.board.open;
+                self.refresh_leaderboard();
+            }
+            if ui.button("Race").clicked() {
+                self.race.open = !self.race.open;
+            }
+            if ui.button("Statistics").clicked() {
+                self.stats.open = !self.stats.open;
+                self.refresh_stats();
+            }
+        });
     }
 
-    fn update_keyboard_state(&mut self, guess: &str, feedback: &[char]) {
-        for (i, c) in guess.chars().enumerate() {
-            let current_state = self.keyboard_state.entry(c).or_insert('X');
+    fn render_settings(&mut self, ui: &mut egui::Ui) {
+        //changes to lists, seed and state file take effect with Apply
+        ui.heading("Settings");
+        ui.separator();
 
-            match feedback[i] {
-                'G' => *current_state = 'G',
-                'Y' if *current_state != 'G' => *current_state = 'Y',
-                'R' if *current_state == 'X' => *current_state = 'R',
-                _ => {}
+        let mut fixed_seed = self.config.seed.is_some();
+        ui.checkbox(&mut fixed_seed, "Fixed seed");
+        if fixed_seed {
+            let mut seed = self.config.seed.unwrap_or(0);
+            ui.horizontal(|ui| {
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut seed));
+            });
+            self.config.seed = Some(seed);
+            ui.horizontal(|ui| {
+                ui.label("Day");
+                ui.add(egui::DragValue::new(&mut self.config.day).clamp_range(1..=usize::MAX));
+            });
+        } else {
+            self.config.seed = None;
+            ui.horizontal(|ui| {
+                ui.label(format!("Session seed: {}", self.seed));
+                if ui
+                    .button("Fix")
+                    .on_hover_text("Play this session's answers again")
+                    .clicked()
+                {
+                    self.config.seed = Some(self.seed);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} of {} answers this cycle",
+                self.played.len(),
+                self.final_list.len()
+            ));
+            if ui
+                .button("New cycle")
+                .on_hover_text("Answers you already had may come again")
+                .clicked()
+            {
+                if let Some(ref mut x) = self.state_store {
+                    let _ = x.reset_cycle(Some(&self.player), store::now());
+                }
+                self.played.clear();
+            }
+        });
+
+        ui.separator();
+        let file_row = |ui: &mut egui::Ui, title: &str, path: &mut Option<PathBuf>| {
+            ui.label(title);
+            ui.horizontal(|ui| {
+                let shown = match path {
+                    Some(x) => x.display().to_string(),
+                    None => "builtin".to_string(),
+                };
+                ui.label(shown);
+                if ui.button("Choose...").clicked()
+                    && let Some(x) = rfd::FileDialog::new().pick_file()
+                {
+                    *path = Some(x);
+                }
+                if path.is_some() && ui.button("Builtin").clicked() {
+                    *path = None;
+                }
+            });
+        };
+        file_row(ui, "Final set", &mut self.config.final_set);
+        file_row(ui, "Acceptable set", &mut self.config.acceptable_set);
+        ui.checkbox(&mut self.config.custom_lists, "Custom lists")
+            .on_hover_text("Word lists of your own, answers only need to be acceptable");
+        ui.label("Language pack");
+        ui.horizontal(|ui| {
+            let shown = match self.config.lang {
+                Some(ref x) => x.display().to_string(),
+                None => "English (builtin)".to_string(),
+            };
+            ui.label(shown);
+            if ui.button("Choose...").clicked()
+                && let Some(x) = rfd::FileDialog::new()
+                    .add_filter("language pack", &["json"])
+                    .pick_file()
+            {
+                self.config.lang = Some(x);
+            }
+            if self.config.lang.is_some() && ui.button("English").clicked() {
+                self.config.lang = None;
+            }
+        });
+        ui.label("Word frequencies");
+        ui.horizontal(|ui| {
+            let shown = match self.config.freq {
+                Some(ref x) => x.display().to_string(),
+                None => "none, answers are uniform".to_string(),
+            };
+            ui.label(shown);
+            if ui.button("Choose...").clicked()
+                && let Some(x) = rfd::FileDialog::new().pick_file()
+            {
+                self.config.freq = Some(x);
+            }
+            if self.config.freq.is_some() && ui.button("None").clicked() {
+                self.config.freq = None;
+            }
+        })
+        .response
+        .on_hover_text(
+            "\"word weight\" per line, common answers come up more often and rank higher in hints",
+        );
+
+        ui.label("State file");
+        ui.horizontal(|ui| {
+            ui.label(self.config.state.display().to_string());
+            if ui.button("Choose...").clicked()
+                && let Some(x) = rfd::FileDialog::new()
+                    .add_filter("state", &["json", "db", "sqlite", "sqlite3"])
+                    .save_file()
+            {
+                self.config.state = x;
+            }
+        });
+
+        ui.separator();
+        ui.checkbox(&mut self.config.difficult, "Difficult mode");
+        if ui.checkbox(&mut self.config.tips, "Tips").changed() {
+            self.hints_outdated = true;
+        }
+        ui.checkbox(&mut self.config.reduce_motion, "Reduce motion");
+        let mut timed = self.config.time_limit.is_some();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut timed, "Time limit");
+            if timed {
+                let mut seconds = self.config.time_limit.unwrap_or(120);
+                ui.add(
+                    egui::DragValue::new(&mut seconds)
+                        .clamp_range(1..=3600)
+                        .suffix(" s"),
+                );
+                self.config.time_limit = Some(seconds);
+            } else {
+                self.config.time_limit = None;
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut names: Vec<String> = theme::BUILTIN.iter().map(|x| x.to_string()).collect();
+            for name in self.config.themes.keys() {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            let before = self.config.theme.clone();
+            egui::ComboBox::from_label("Theme")
+                .selected_text(&self.config.theme)
+                .show_ui(ui, |ui| {
+                    for name in names {
+                        ui.selectable_value(&mut self.config.theme, name.clone(), name);
+                    }
+                });
+            if self.config.theme != before {
+                self.settings_error.clear();
+                self.apply_theme();
             }
+            if ui.button("Load palettes...").clicked()
+                && let Some(x) = rfd::FileDialog::new()
+                    .add_filter("config", &["json"])
+                    .pick_file()
+            {
+                match self.load_palettes(&x) {
+                    Ok(()) => self.settings_error.clear(),
+                    Err(e) => self.settings_error = format!("palettes: {}", e),
+                }
+            }
+        });
+
+        ui.separator();
+        if ui.button("Apply").clicked() {
+            self.init();
+        }
+        if !self.settings_error.is_empty() {
+            ui.colored_label(egui::Color32::RED, &self.settings_error);
         }
     }
 
     fn get_key_color(&self, key: char) -> egui::Color32 {
-        match self.keyboard_state.get(&key).unwrap_or(&'X') {
-            'G' => egui::Color32::from_rgb(106, 170, 100),
-            'Y' => egui::Color32::from_rgb(201, 180, 88),
-            'R' => egui::Color32::from_rgb(120, 124, 126),
-            _ => egui::Color32::from_rgb(211, 214, 218),
-        }
-    }
-
-    fn render_game_grid(&self, ui: &mut egui::Ui) {
-        ui.vertical_centered(|ui| {
-            egui::Grid::new("game_grid")
-                .spacing([8.0, 50.0])
-                .show(ui, |ui| {
-                    for row in 0..6 {
-                        for col in 0..5 {
-                            ui.add_space(100.0);
-                            let cell_size = egui::vec2(80.0, 50.0);
-
-                            if row < self.guesses.len() {
-                                let letter = self.guesses[row].chars().nth(col).unwrap();
-                                let feedback_char = self.feedback[row][col];
-
-                                let color = match feedback_char {
-                                    'G' => egui::Color32::from_rgb(106, 170, 100),
-                                    'Y' => egui::Color32::from_rgb(201, 180, 88),
-                                    _ => egui::Color32::from_rgb(120, 124, 126),
-                                };
-
-                                let rect = egui::Rect::from_min_size(ui.cursor().min, cell_size);
-                                ui.painter().rect_filled(rect, 4.0, color);
-
-                                ui.add_space(25.0);
-
-                                ui.label(
-                                    egui::RichText::new(letter.to_string())
-                                        .color(egui::Color32::WHITE)
-                                        .size(40.0)
-                                        .strong(),
-                                );
-                            } else if row == self.guesses.len() && col < self.current_guess.len() {
-                                let letter = self.current_guess.chars().nth(col).unwrap();
-
-                                let rect = egui::Rect::from_min_size(ui.cursor().min, cell_size);
-                                ui.painter().rect_stroke(
-                                    rect,
-                                    4.0,
-                                    egui::Stroke::new(2.0, egui::Color32::GRAY),
-                                );
-
-                                ui.add_space(25.0);
-
-                                ui.label(
-                                    egui::RichText::new(letter.to_string()).size(40.0).strong(),
-                                );
-                            } else {
-                                let rect = egui::Rect::from_min_size(ui.cursor().min, cell_size);
-                                ui.painter().rect_stroke(
-                                    rect,
-                                    4.0,
-                                    egui::Stroke::new(2.0, egui::Color32::GRAY),
-                                );
-
-                                ui.label(" ");
-                            }
-                        }
-                        ui.end_row();
+        color32(self.palette.color(self.round.letter_status(key)))
+    }
+
+    fn tile_label(&self, row: usize, col: usize) -> String {
+        let place = format!("Row {}, letter {}", row + 1, col + 1);
+        if row < self.round.guesses.len() {
+            let letter = self.round.guesses[row].chars().nth(col).unwrap();
+            format!(
+                "{}: {}, {}",
+                place,
+                upper(letter),
+                engine::status_word(self.round.feedback[row][col])
+            )
+        } else if row == self.round.guesses.len() && col < self.current_guess.chars().count() {
+            let letter = self.current_guess.chars().nth(col).unwrap();
+            format!("{}: {}, typed", place, letter)
+        } else {
+            format!("{}: empty", place)
+        }
+    }
+
+    fn render_game_grid(&self, ui: &mut egui::Ui, layout: &Layout) {
+        let cell = layout.cell;
+        let gap = layout.gap;
+        let size = egui::vec2(
+            WORD_LEN as f32 * (cell + gap) - gap,
+            MAX_TURNS as f32 * (cell + gap) - gap,
+        );
+        let (area, _) = ui
+            .vertical_centered(|ui| ui.allocate_exact_size(size, egui::Sense::hover()))
+            .inner;
+        let painter = ui.painter();
+        let border = egui::Stroke::new(2.0, egui::Color32::GRAY);
+        let font = egui::FontId::proportional(cell * 0.55);
+
+        for row in 0..MAX_TURNS {
+            let shake = if row == self.round.guesses.len() {
+                self.anim.shake_offset(self.now, cell)
+            } else {
+                0.0
+            };
+            for col in 0..WORD_LEN {
+                let min = area.min
+                    + egui::vec2(
+                        col as f32 * (cell + gap) + shake,
+                        row as f32 * (cell + gap)
+                            + self.anim.bounce_offset(row, col, self.now, cell),
+                    );
+                let mut rect = egui::Rect::from_min_size(min, egui::vec2(cell, cell));
+                //screen readers get what the painted tile shows
+                let label = self.tile_label(row, col);
+                ui.interact(rect, ui.id().with(("tile", row, col)), egui::Sense::hover())
+                    .widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Label, &label));
+
+                if row < self.round.guesses.len() {
+                    let letter = self.round.guesses[row].chars().nth(col).unwrap();
+                    let letter = upper(letter);
+                    //flip: squash to a line, then open showing the color
+                    let progress = self.anim.flip(row, col, self.now);
+                    let revealed = progress.is_some_and(|t| t >= 0.5) || self.anim.reveal.is_none();
+                    if let Some(t) = progress {
+                        let scale = ((t - 0.5).abs() * 2.0) as f32;
+                        rect = egui::Rect::from_center_size(
+                            rect.center(),
+                            egui::vec2(cell, cell * scale.max(0.02)),
+                        );
                     }
-                });
-        });
+                    if revealed {
+                        let color = color32(self.palette.color(self.round.feedback[row][col]));
+                        painter.rect_filled(rect, 4.0, color);
+                        painter.text(
+                            rect.center(),
+                            egui::Align2::CENTER_CENTER,
+                            letter,
+                            font.clone(),
+                            color32(self.palette.text),
+                        );
+                    } else {
+                        painter.rect_stroke(rect, 4.0, border);
+                        painter.text(
+                            rect.center(),
+                            egui::Align2::CENTER_CENTER,
+                            letter,
+                            font.clone(),
+                            ui.visuals().text_color(),
+                        );
+                    }
+                } else if row == self.round.guesses.len()
+                    && col < self.current_guess.chars().count()
+                {
+                    let letter = self.current_guess.chars().nth(col).unwrap();
+                    painter.rect_stroke(rect, 4.0, border);
+                    painter.text(
+                        rect.center(),
+                        egui::Align2::CENTER_CENTER,
+                        letter,
+                        font.clone(),
+                        ui.visuals().text_color(),
+                    );
+                } else {
+                    painter.rect_stroke(rect, 4.0, border);
+                }
+            }
+        }
     }
 
-    fn render_keyboard(&mut self, ui: &mut egui::Ui) {
-        let keyboard_rows = ["QWERTYUIOP", "ASDFGHJKL", "ZXCVBNM"];
+    fn render_keyboard(&mut self, ui: &mut egui::Ui, layout: &Layout) {
+        let keyboard_rows = self.language.keyboard.clone();
+        let font = layout.key.y * 0.4;
 
         for row in keyboard_rows.iter() {
             ui.horizontal(|ui| {
-                ui.spacing_mut().item_spacing = egui::vec2(10.0, 4.0);
-                let total_width = row.len() as f32 * 46.0 - 4.0;
-                let available_width = ui.available_width();
-                let padding = (available_width - total_width) / 2.0;
-                ui.add_space(padding);
+                ui.spacing_mut().item_spacing = egui::vec2(layout.key_gap, layout.key_gap);
+                let total_width =
+                    row.chars().count() as f32 * (layout.key.x + layout.key_gap) - layout.key_gap;
+                let padding = (ui.available_width() - total_width) / 2.0 - layout.key_gap;
+                ui.add_space(padding.max(0.0));
                 for key in row.chars() {
-                    let button = egui::Button::new(
-                        egui::RichText::new(key.to_string())
-                            .size(16.0)
-                            .color(egui::Color32::WHITE),
-                    )
-                    .fill(self.get_key_color(key))
-                    .min_size(egui::vec2(36.0, 46.0));
-
-                    if ui.add(button).clicked() && !self.game_over && self.current_guess.len() < 5 {
-                        self.current_guess.push(key);
+                    let button =
+                        egui::Button::new(egui::RichText::new(key.to_string()).size(font).color(
+                            match self.round.letter_status(key) {
+                                'G' | 'Y' | 'R' => color32(self.palette.text),
+                                _ => egui::Color32::BLACK,
+                            },
+                        ))
+                        .fill(self.get_key_color(key))
+                        .min_size(layout.key);
+
+                    let status = engine::status_word(self.round.letter_status(key));
+                    let response = ui.add(button);
+                    response.widget_info(|| {
+                        egui::WidgetInfo::labeled(
+                            egui::WidgetType::Button,
+                            format!("{}, {}", key, status),
+                        )
+                    });
+                    if response.clicked() && !self.game_over {
+                        self.type_text(&key.to_string());
                     }
                 }
-                ui.add_space(padding);
             });
-
-            ui.add_space(6.0);
         }
 
         ui.horizontal(|ui| {
-            let total_width = 640.0;
-            let available_width = ui.available_width();
-            let padding = (available_width - total_width) / 2.0;
-            ui.add_space(padding);
-            let enter_button = ui.add(egui::Button::new("ENTER").min_size(egui::vec2(100.0, 46.0)));
+            ui.spacing_mut().item_spacing = egui::vec2(layout.key_gap, layout.key_gap);
+            //four action buttons share the keyboard width
+            let button_size = egui::vec2(
+                ((ui.available_width() * 0.95 - 3.0 * layout.key_gap) / 4.0)
+                    .min(layout.key.x * 3.5),
+                layout.key.y,
+            );
+            let total_width = 4.0 * (button_size.x + layout.key_gap) - layout.key_gap;
+            let padding = (ui.available_width() - total_width) / 2.0 - layout.key_gap;
+            ui.add_space(padding.max(0.0));
+            let label = |text: &str| egui::RichText::new(text).size(font * 0.7);
 
-            if enter_button.clicked() && !self.game_over && self.current_guess.len() == 5 {
+            let enter_button = ui.add(egui::Button::new(label("ENTER")).min_size(button_size));
+
+            if enter_button.clicked()
+                && !self.game_over
+                && self.current_guess.chars().count() == WORD_LEN
+            {
                 self.submit_guess();
             }
 
-            ui.add_space(70.0);
-
             let backspace_button =
-                ui.add(egui::Button::new("BACKSPACE").min_size(egui::vec2(100.0, 46.0)));
+                ui.add(egui::Button::new(label("BACKSPACE")).min_size(button_size));
 
             if backspace_button.clicked() && !self.game_over && !self.current_guess.is_empty() {
                 self.current_guess.pop();
             }
 
-            ui.add_space(70.0);
-
             let new_game_button =
-                ui.add(egui::Button::new("NEW GAME").min_size(egui::vec2(100.0, 46.0)));
+                ui.add(egui::Button::new(label("NEW GAME")).min_size(button_size));
 
-            if new_game_button.clicked() {
+            if new_game_button.clicked() && !self.race.in_race {
                 self.new_game();
             }
 
-            ui.add_space(70.0);
-
             let mode_button =
-                ui.add(egui::Button::new("DIFFICULT MODE").min_size(egui::vec2(100.0, 46.0)));
+                ui.add(egui::Button::new(label("DIFFICULT MODE")).min_size(button_size));
 
             if mode_button.clicked() {
                 self.config.difficult = !self.config.difficult;
@@ -366,34 +1441,144 @@ impl WordleApp {
     }
 }
 
+fn color32(color: theme::Rgb) -> egui::Color32 {
+    egui::Color32::from_rgb(color.0, color.1, color.2)
+}
+
+fn replay_round(game: &Game) -> Round {
+    //rebuild the hints of a stored game, skipping anything malformed
+    //any language: the letters of the stored words make the alphabet
+    let mut alphabet: Vec<char> = game
+        .answer
+        .to_lowercase()
+        .chars()
+        .chain(
+            game.guesses
+                .iter()
+                .flat_map(|x| x.to_lowercase().chars().collect::<Vec<_>>()),
+        )
+        .filter(|c| c.is_alphabetic())
+        .collect();
+    alphabet.sort();
+    alphabet.dedup();
+    let mut round = Round::with_alphabet(&game.answer, false, alphabet);
+    if round.answer().chars().count() != WORD_LEN {
+        return round;
+    }
+    for guess in game.guesses.iter().map(|x| x.to_lowercase()) {
+        if guess.chars().count() == WORD_LEN && guess.chars().all(|c| c.is_alphabetic()) {
+            round.record(&guess);
+        }
+    }
+    round
+}
+
+fn paint_board(ui: &mut egui::Ui, round: &Round, palette: &theme::Palette, cell: f32) {
+    //static copy of the game grid, played rows only
+    let gap = cell * 0.1;
+    let rows = round.guesses.len().max(1);
+    let size = egui::vec2(
+        WORD_LEN as f32 * (cell + gap) - gap,
+        rows as f32 * (cell + gap) - gap,
+    );
+    let (area, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let font = egui::FontId::proportional(cell * 0.55);
+    for (row, guess) in round.guesses.iter().enumerate() {
+        for (col, letter) in guess.chars().enumerate() {
+            let min = area.min + egui::vec2(col as f32 * (cell + gap), row as f32 * (cell + gap));
+            let rect = egui::Rect::from_min_size(min, egui::vec2(cell, cell));
+            let color = color32(palette.color(round.feedback[row][col]));
+            ui.painter().rect_filled(rect, 3.0, color);
+            ui.painter().text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                upper(letter),
+                font.clone(),
+                color32(palette.text),
+            );
+        }
+    }
+}
+
+fn format_date(secs: u64) -> String {
+    //unix seconds -> YYYY-MM-DD, inverse of leaderboard::parse_date
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days / 146097;
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
 impl eframe::App for WordleApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.config);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.now = ctx.input(|i| i.time);
+        ctx.set_visuals(if self.palette.dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        });
+        egui::TopBottomPanel::top("profile_panel").show(ctx, |ui| {
+            self.render_profile_picker(ui);
+        });
+        self.poll_race(ctx);
+        self.render_leaderboard(ctx);
+        self.render_stats(ctx);
+        let mut open = self.race.open;
+        egui::Window::new("Race")
+            .open(&mut open)
+            .show(ctx, |ui| self.render_race(ui));
+        self.race.open = open;
+        if self.show_settings {
+            egui::SidePanel::right("settings_panel").show(ctx, |ui| {
+                self.render_settings(ui);
+            });
+        }
+        if self.hints_outdated {
+            self.hints_outdated = false;
+            self.update_candidates(ctx);
+        }
+        if self.config.tips {
+            egui::SidePanel::left("hints_panel").show(ctx, |ui| {
+                self.render_hints(ui);
+            });
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
+            let layout = Layout::new(ui.available_size(), WORD_LEN, MAX_TURNS);
             ui.vertical_centered(|ui| {
                 ui.heading(
                     egui::RichText::new("WORDLE")
                         .size(36.0)
-                        .color(egui::Color32::from_rgb(106, 170, 100)),
+                        .color(color32(self.palette.correct)),
                 );
+                if let Some(timer) = self.check_clock(ctx) {
+                    ui.label(egui::RichText::new(timer).monospace().size(18.0));
+                }
             });
 
-            ui.add_space(20.0);
+            ui.add_space(layout.spacing);
 
-            self.render_game_grid(ui);
+            self.render_game_grid(ui, &layout);
 
-            ui.add_space(40.0);
+            ui.add_space(layout.spacing);
 
-            self.render_keyboard(ui);
+            self.render_keyboard(ui, &layout);
 
-            if !self.game_over {
+            if !self.game_over && !ctx.wants_keyboard_input() {
+                //typing a profile name must not reach the grid
                 ctx.input(|i| {
                     for event in &i.events {
                         if let egui::Event::Text(text) = event {
-                            for c in text.chars() {
-                                if c.is_ascii_alphabetic() && self.current_guess.len() < 5 {
-                                    self.current_guess.push(c.to_ascii_uppercase());
-                                }
-                            }
+                            self.type_text(text);
                         }
                     }
 
@@ -402,43 +1587,68 @@ impl eframe::App for WordleApp {
                         self.message.clear();
                     }
 
-                    if i.key_pressed(egui::Key::Enter) && self.current_guess.len() == 5 {
+                    if i.key_pressed(egui::Key::Enter)
+                        && self.current_guess.chars().count() == WORD_LEN
+                    {
                         self.submit_guess();
                     }
                 });
             }
 
-            ui.add_space(20.0);
+            ui.add_space(layout.spacing);
 
             if !self.message.is_empty() {
                 ui.vertical_centered(|ui| {
                     ui.label(
                         egui::RichText::new(&self.message)
-                            .size(24.0)
+                            .size((layout.cell * 0.4).clamp(14.0, 24.0))
                             .color(egui::Color32::RED),
                     );
                 });
             }
+            if self.game_over && !self.round.guesses.is_empty() {
+                ui.vertical_centered(|ui| {
+                    if ui.button("Share").clicked() {
+                        //emoji grid only, the answer stays hidden
+                        let grid = theme::share_grid(
+                            &self.round.feedback,
+                            self.round.won(),
+                            MAX_TURNS,
+                            &self.palette,
+                        );
+                        ui.output_mut(|o| o.copied_text = grid);
+                        self.message = "Copied to clipboard".to_string();
+                    }
+                });
+            }
         });
 
-        ctx.request_repaint();
+        //only keep drawing while something moves, input wakes egui up otherwise
+        if self.anim.running(self.now) {
+            ctx.request_repaint();
+        }
     }
 }
 
 fn main() -> eframe::Result<()> {
     let mut options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1000.0, 1200.0])
-            .with_min_inner_size([500.0, 700.0])
-            .with_resizable(false),
+            .with_inner_size([720.0, 960.0])
+            .with_min_inner_size([320.0, 480.0])
+            .with_resizable(true),
         ..Default::default()
     };
     options.centered = true;
     eframe::run_native(
         "Wordle Game",
         options,
-        Box::new(|_cc| {
+        Box::new(|cc| {
             let mut app = WordleApp::default();
+            if let Some(storage) = cc.storage
+                && let Some(config) = eframe::get_value(storage, eframe::APP_KEY)
+            {
+                app.config = config;
+            }
             app.init();
             Box::new(app)
         }),
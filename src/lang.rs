@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+//a language pack: letters, how typed text maps onto them, keyboard and word lists
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Language {
+    pub name: String,
+    pub alphabet: String,               //lowercase letters of the game
+    pub keyboard: Vec<String>,          //on-screen rows, uppercase
+    pub fold: BTreeMap<String, String>, //after lowercasing, e.g. "á" -> "a", "ß" -> "ss"
+    pub final_set: Option<PathBuf>,     //relative to the pack file, builtin lists when None
+    pub acceptable_set: Option<PathBuf>,
+}
+
+impl Default for Language {
+    fn default() -> Language {
+        Language::english()
+    }
+}
+
+//precomposed letters of the latin scripts the packs use, from base + combining mark
+const COMPOSE: [(char, char, char); 26] = [
+    ('a', '\u{300}', 'à'),
+    ('a', '\u{301}', 'á'),
+    ('a', '\u{302}', 'â'),
+    ('a', '\u{303}', 'ã'),
+    ('a', '\u{308}', 'ä'),
+    ('a', '\u{30a}', 'å'),
+    ('c', '\u{327}', 'ç'),
+    ('e', '\u{300}', 'è'),
+    ('e', '\u{301}', 'é'),
+    ('e', '\u{302}', 'ê'),
+    ('e', '\u{308}', 'ë'),
+    ('i', '\u{300}', 'ì'),
+    ('i', '\u{301}', 'í'),
+    ('i', '\u{302}', 'î'),
+    ('i', '\u{308}', 'ï'),
+    ('n', '\u{303}', 'ñ'),
+    ('o', '\u{300}', 'ò'),
+    ('o', '\u{301}', 'ó'),
+    ('o', '\u{302}', 'ô'),
+    ('o', '\u{303}', 'õ'),
+    ('o', '\u{308}', 'ö'),
+    ('u', '\u{300}', 'ù'),
+    ('u', '\u{301}', 'ú'),
+    ('u', '\u{302}', 'û'),
+    ('u', '\u{308}', 'ü'),
+    ('y', '\u{301}', 'ý'),
+];
+
+pub fn is_combining(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+pub fn graphemes(text: &str) -> Vec<String> {
+    //user-perceived letters: a base char with the combining marks after it
+    let mut out: Vec<String> = Vec::new();
+    for c in text.chars() {
+        match out.last_mut() {
+            Some(last) if is_combining(c) => last.push(c),
+            _ => out.push(c.to_string()),
+        }
+    }
+    out
+}
+
+impl Language {
+    pub fn english() -> Language {
+        Language {
+            name: "en".to_string(),
+            alphabet: "abcdefghijklmnopqrstuvwxyz".to_string(),
+            keyboard: ["QWERTYUIOP", "ASDFGHJKL", "ZXCVBNM"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            fold: BTreeMap::new(),
+            final_set: None,
+            acceptable_set: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Language, Box<dyn std::error::Error>> {
+        //json pack, its word list paths are relative to it
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read language pack {}: {}", path.display(), e))?;
+        let mut language: Language = serde_json::from_str(&text)
+            .map_err(|e| format!("bad language pack {}: {}", path.display(), e))?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for list in [&mut language.final_set, &mut language.acceptable_set] {
+            if let Some(x) = list.as_mut()
+                && x.is_relative()
+            {
+                *x = dir.join(&*x);
+            }
+        }
+        language.alphabet = language.normalize(&language.alphabet.clone());
+        //letters are packed into a byte each
+        if language.alphabet.is_empty() || language.alphabet.chars().count() > 256 {
+            return Err(format!(
+                "language pack {} needs an alphabet of 1 to 256 letters",
+                path.display()
+            )
+            .into());
+        }
+        Ok(language)
+    }
+
+    pub fn letters(&self) -> Vec<char> {
+        self.alphabet.chars().collect()
+    }
+
+    pub fn is_letter(&self, c: char) -> bool {
+        self.alphabet.contains(c)
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        //trim, lowercase, compose accents typed as combining marks, then the pack's folds
+        let lower: String = text.trim().chars().flat_map(|c| c.to_lowercase()).collect();
+        let mut composed = String::new();
+        for c in lower.chars() {
+            let last = composed.chars().last();
+            match COMPOSE
+                .iter()
+                .find(|(base, mark, _)| Some(*base) == last && *mark == c)
+            {
+                Some((_, _, x)) => {
+                    composed.pop();
+                    composed.push(*x);
+                }
+                None => composed.push(c),
+            }
+        }
+        //longest rule first, so "sch" would win over "s"
+        let mut rules: Vec<(&String, &String)> = self.fold.iter().collect();
+        rules.sort_by_key(|(from, _)| std::cmp::Reverse(from.chars().count()));
+        let mut out = String::new();
+        let mut rest = composed.as_str();
+        'outer: while let Some(c) = rest.chars().next() {
+            for (from, to) in rules.iter() {
+                if !from.is_empty()
+                    && let Some(x) = rest.strip_prefix(from.as_str())
+                {
+                    out.push_str(to);
+                    rest = x;
+                    continue 'outer;
+                }
+            }
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(name: &str) -> Language {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("langs/samples/{}/pack.json", name));
+        Language::load(&path).unwrap()
+    }
+
+    #[test]
+    fn graphemes_keep_marks_with_their_letter() {
+        assert_eq!(graphemes("año"), ["a", "ñ", "o"]);
+        assert_eq!(graphemes("an\u{303}o"), ["a", "n\u{303}", "o"]);
+        assert_eq!(graphemes("e\u{301}\u{308}"), ["e\u{301}\u{308}"]);
+        assert!(graphemes("").is_empty());
+    }
+
+    #[test]
+    fn english_only_trims_and_lowercases() {
+        let english = Language::english();
+        assert_eq!(english.normalize("  CRANE\n"), "crane");
+        assert_eq!(english.normalize("Año"), "año");
+        assert!(english.is_letter('z') && !english.is_letter('ñ'));
+    }
+
+    #[test]
+    fn packs_compose_and_fold() {
+        let es = pack("es");
+        assert_eq!(es.normalize("CAN\u{303}ÓN"), "cañon");
+        assert_eq!(es.normalize("pingüino"), "pinguino");
+        assert_eq!(es.letters().len(), 27);
+        let de = pack("de");
+        assert_eq!(de.normalize("STRAẞE"), "strasse");
+        assert_eq!(de.normalize("Grüße"), "grüsse");
+        assert_eq!(de.normalize("Gru\u{308}n"), "grün");
+        assert!(
+            de.final_set
+                .as_ref()
+                .unwrap()
+                .ends_with("langs/samples/de/final.txt")
+        );
+    }
+
+    #[test]
+    fn longest_fold_wins() {
+        let mut language = Language::english();
+        language.fold.insert("s".to_string(), "z".to_string());
+        language.fold.insert("sch".to_string(), "sh".to_string());
+        assert_eq!(language.normalize("schoss"), "shozz");
+    }
+
+    #[test]
+    fn rejects_bad_alphabets() {
+        let path = crate::testutil::temp_file("pack.json", r#"{"name": "empty", "alphabet": ""}"#);
+        assert!(Language::load(&path).is_err());
+        std::fs::write(&path, r#"{"name": "x", "alphabet": "ABC"}"#).unwrap();
+        assert_eq!(Language::load(&path).unwrap().alphabet, "abc");
+        std::fs::remove_file(path).unwrap();
+    }
+}
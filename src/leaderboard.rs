@@ -0,0 +1,363 @@
+use crate::store::{Game, JsonState, Run, Stats};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const ANONYMOUS: &str = "anonymous"; //games recorded before profiles existed
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Metric {
+    WinRate,
+    AvgGuesses,
+    Streak,
+    #[default]
+    Score, //composite of the three above
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+//which games take part in the ranking
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    pub from: Option<u64>, //unix seconds, inclusive
+    pub to: Option<u64>,   //unix seconds, exclusive
+    pub mode: Option<String>,
+}
+
+impl Filter {
+    fn accept(&self, game: &Game) -> bool {
+        if let Some(ref mode) = self.mode
+            && game.mode.as_deref().unwrap_or("normal") != mode
+        {
+            return false;
+        }
+        self.in_range(game.played_at)
+    }
+
+    fn in_range(&self, played_at: Option<u64>) -> bool {
+        if self.from.is_none() && self.to.is_none() {
+            return true;
+        }
+        match played_at {
+            Some(t) => self.from.is_none_or(|x| t >= x) && self.to.is_none_or(|x| t < x),
+            None => false, //undated games can't be placed in a range
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Entry {
+    pub rank: usize,
+    pub player: String,
+    pub games: i32,
+    pub wins: i32,
+    pub win_rate: f64,
+    pub avg_guesses: f64, //over won games, 0 when none
+    pub max_streak: i32,
+    pub score: f64,
+}
+
+impl Entry {
+    fn new(player: String, stats: &Stats) -> Entry {
+        let win_rate = if stats.total_rounds > 0 {
+            stats.success as f64 / stats.total_rounds as f64
+        } else {
+            0.0
+        };
+        let avg_guesses = if stats.success > 0 {
+            stats.success_tries as f64 / stats.success as f64
+        } else {
+            0.0
+        };
+        //winning matters most, then solving fast, then consistency
+        let mut score = win_rate * 100.0 + stats.max_streak as f64;
+        if stats.success > 0 {
+            score += (6.0 - avg_guesses) * 10.0;
+        }
+        Entry {
+            rank: 0,
+            player,
+            games: stats.total_rounds,
+            wins: stats.success,
+            win_rate,
+            avg_guesses,
+            max_streak: stats.max_streak,
+            score,
+        }
+    }
+
+    fn key(&self, metric: Metric) -> f64 {
+        //bigger is better
+        match metric {
+            Metric::WinRate => self.win_rate,
+            Metric::AvgGuesses if self.wins == 0 => f64::NEG_INFINITY,
+            Metric::AvgGuesses => -self.avg_guesses,
+            Metric::Streak => self.max_streak as f64,
+            Metric::Score => self.score,
+        }
+    }
+}
+
+pub fn rank(json_data: &JsonState, filter: &Filter, metric: Metric) -> Vec<Entry> {
+    let mut per_player: BTreeMap<String, Stats> = BTreeMap::new();
+    for game in json_data.games.iter().filter(|x| filter.accept(x)) {
+        let stats = per_player
+            .entry(game.player.clone().unwrap_or_else(|| ANONYMOUS.to_string()))
+            .or_default();
+        stats.total_rounds += 1;
+        stats.push_result(game.won());
+        if game.won() {
+            stats.success += 1;
+            stats.success_tries += game.guesses.len() as i32;
+        }
+    }
+    let mut entries: Vec<Entry> = per_player
+        .into_iter()
+        .map(|(player, stats)| Entry::new(player, &stats))
+        .collect();
+    entries.sort_by(|a, b| {
+        b.key(metric)
+            .total_cmp(&a.key(metric))
+            .then(b.games.cmp(&a.games))
+            .then(a.player.cmp(&b.player))
+    });
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i + 1;
+    }
+    entries
+}
+
+pub fn format_table(entries: &[Entry]) -> String {
+    let mut out = format!(
+        "{:<5}{:<16}{:>6}{:>6}{:>8}{:>7}{:>8}{:>8}\n",
+        "RANK", "PLAYER", "GAMES", "WINS", "WIN%", "AVG", "STREAK", "SCORE"
+    );
+    for x in entries.iter() {
+        out += &format!(
+            "{:<5}{:<16}{:>6}{:>6}{:>7.1}%{:>7.2}{:>8}{:>8.1}\n",
+            x.rank,
+            x.player,
+            x.games,
+            x.wins,
+            x.win_rate * 100.0,
+            x.avg_guesses,
+            x.max_streak,
+            x.score
+        );
+    }
+    out
+}
+
+//best survival run of each player
+#[derive(Debug, Serialize, Clone)]
+pub struct RunEntry {
+    pub rank: usize,
+    pub player: String,
+    pub runs: usize,
+    pub best_score: usize,
+    pub best_length: usize, //of the best scoring run
+    pub played_at: Option<u64>,
+}
+
+pub fn rank_runs(json_data: &JsonState, filter: &Filter) -> Vec<RunEntry> {
+    let mut per_player: BTreeMap<String, (usize, &Run)> = BTreeMap::new();
+    for run in json_data
+        .runs
+        .iter()
+        .filter(|x| filter.in_range(x.played_at))
+    {
+        let player = run.player.clone().unwrap_or_else(|| ANONYMOUS.to_string());
+        let entry = per_player.entry(player).or_insert((0, run));
+        entry.0 += 1;
+        if (run.score, run.length) > (entry.1.score, entry.1.length) {
+            entry.1 = run;
+        }
+    }
+    let mut entries: Vec<RunEntry> = per_player
+        .into_iter()
+        .map(|(player, (runs, best))| RunEntry {
+            rank: 0,
+            player,
+            runs,
+            best_score: best.score,
+            best_length: best.length,
+            played_at: best.played_at,
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.best_score
+            .cmp(&a.best_score)
+            .then(b.best_length.cmp(&a.best_length))
+            .then(a.player.cmp(&b.player))
+    });
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i + 1;
+    }
+    entries
+}
+
+pub fn format_runs(entries: &[RunEntry]) -> String {
+    let mut out = format!(
+        "{:<5}{:<16}{:>6}{:>8}{:>8}\n",
+        "RANK", "PLAYER", "RUNS", "LENGTH", "SCORE"
+    );
+    for x in entries.iter() {
+        out += &format!(
+            "{:<5}{:<16}{:>6}{:>8}{:>8}\n",
+            x.rank, x.player, x.runs, x.best_length, x.best_score
+        );
+    }
+    out
+}
+
+pub fn parse_date(date: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    //YYYY-MM-DD (UTC midnight) -> unix seconds
+    let parts: Vec<&str> = date.trim().split('-').collect();
+    if parts.len() != 3 {
+        return Err(format!("bad date {}, expect YYYY-MM-DD", date).into());
+    }
+    let (y, m, d): (i64, i64, i64) = (parts[0].parse()?, parts[1].parse()?, parts[2].parse()?);
+    let leap = y % 4 == 0 && (y % 100 != 0 || y % 400 == 0);
+    let month_days = match m {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=12).contains(&m) || !(1..=month_days).contains(&d) || y < 1970 {
+        return Err(format!("bad date {}, expect YYYY-MM-DD", date).into());
+    }
+    //days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Ok(days as u64 * 86400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(player: Option<&str>, guesses: usize, won: bool, played_at: u64, mode: &str) -> Game {
+        let mut words = vec!["SLATE".to_string(); guesses - 1];
+        words.push(if won { "CRANE" } else { "SLATE" }.to_string());
+        Game {
+            answer: "CRANE".to_string(),
+            guesses: words,
+            player: player.map(|x| x.to_string()),
+            played_at: Some(played_at),
+            mode: Some(mode.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn state() -> JsonState {
+        JsonState {
+            games: vec![
+                //ann: every game, in 4
+                game(Some("ann"), 4, true, 100, "normal"),
+                game(Some("ann"), 4, true, 200, "normal"),
+                //bob: half of them, in 2, one difficult
+                game(Some("bob"), 2, true, 100, "difficult"),
+                game(Some("bob"), 6, false, 300, "normal"),
+                //old file, no profile
+                game(None, 3, true, 400, "normal"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn players(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|x| x.player.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2024-03-01").unwrap(), 1709251200);
+        assert_eq!(parse_date(" 2024-02-29 ").unwrap(), 1709164800);
+        assert_eq!(parse_date("2000-02-29").unwrap(), 951782400);
+        for bad in [
+            "2024-02-30",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-01-00",
+            "1969-12-31",
+            "2024-01",
+            "yesterday",
+        ] {
+            assert!(parse_date(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn ranks_by_metric() {
+        let state = state();
+        let entries = rank(&state, &Filter::default(), Metric::WinRate);
+        //anonymous and ann both won everything, ann played more
+        assert_eq!(players(&entries), ["ann", ANONYMOUS, "bob"]);
+        assert_eq!(entries[2].win_rate, 0.5);
+        assert_eq!(
+            entries.iter().map(|x| x.rank).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        let entries = rank(&state, &Filter::default(), Metric::AvgGuesses);
+        assert_eq!(players(&entries), ["bob", ANONYMOUS, "ann"]);
+        assert_eq!(entries[0].avg_guesses, 2.0);
+        //bob and anonymous tie on 1, more games first
+        let entries = rank(&state, &Filter::default(), Metric::Streak);
+        assert_eq!(players(&entries), ["ann", "bob", ANONYMOUS]);
+        assert_eq!(entries[0].max_streak, 2);
+    }
+
+    #[test]
+    fn filters_games() {
+        let state = state();
+        let filter = Filter {
+            mode: Some("difficult".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(players(&rank(&state, &filter, Metric::Score)), ["bob"]);
+        //from inclusive, to exclusive
+        let filter = Filter {
+            from: Some(200),
+            to: Some(400),
+            mode: None,
+        };
+        let entries = rank(&state, &filter, Metric::Score);
+        assert_eq!(players(&entries), ["ann", "bob"]);
+        assert_eq!(entries.iter().map(|x| x.games).sum::<i32>(), 2);
+    }
+
+    #[test]
+    fn ranks_best_runs() {
+        let run = |player: &str, score: usize, length: usize| Run {
+            player: Some(player.to_string()),
+            played_at: Some(100),
+            length,
+            score,
+            games: Vec::new(),
+        };
+        let state = JsonState {
+            runs: vec![run("ann", 20, 2), run("bob", 25, 2), run("ann", 31, 3)],
+            ..Default::default()
+        };
+        let entries = rank_runs(&state, &Filter::default());
+        assert_eq!(
+            entries
+                .iter()
+                .map(|x| (x.player.as_str(), x.runs, x.best_score))
+                .collect::<Vec<_>>(),
+            [("ann", 2, 31), ("bob", 1, 25)]
+        );
+    }
+}
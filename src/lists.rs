@@ -0,0 +1,290 @@
+use crate::builtin_words::{ACCEPTABLE, FINAL};
+use crate::engine::WORD_LEN;
+use crate::freq::Frequencies;
+use crate::lang::Language;
+use crate::words;
+use clap::Subcommand;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+//word list files: one word per line, blank lines ignored, any case
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Set {
+    Final,      //answers, checked against FINAL
+    Acceptable, //guesses, checked against ACCEPTABLE
+}
+
+impl Set {
+    fn builtin(self) -> &'static [[u8; WORD_LEN]] {
+        match self {
+            Set::Final => FINAL,
+            Set::Acceptable => ACCEPTABLE,
+        }
+    }
+}
+
+#[derive(Subcommand, Clone)]
+pub enum ListsCommand {
+    /// Report every duplicate, non-alphabetic, wrong length and unknown word with its line
+    /// (unknown words are allowed with --custom-lists)
+    Validate {
+        file: PathBuf,
+        #[arg(long = "set", value_enum, default_value = "final")]
+        set: Set,
+    },
+    /// Words only in A (-) and only in B (+)
+    Diff { a: PathBuf, b: PathBuf },
+    /// Union of the files, sorted without duplicates
+    Merge {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(short = 'o', long = "output")] // stdout when not given
+        output: Option<PathBuf>,
+    },
+    /// Keep the words of FILE common enough by the --freq table, sorted
+    Filter {
+        file: PathBuf,
+        #[arg(long = "min", default_value_t = 0.0)] // drop words weighing less
+        min: f64,
+        #[arg(long = "top")] // only the N most common words
+        top: Option<usize>,
+        #[arg(short = 'o', long = "output")] // stdout when not given
+        output: Option<PathBuf>,
+    },
+    /// Write the lists in use (-f, -a) as final.txt and acceptable.txt into DIR
+    Export {
+        #[arg(long = "builtin")] // the lists compiled into the program, ignoring -f and -a
+        builtin: bool,
+        #[arg(long = "force")] // replace final.txt and acceptable.txt already in DIR
+        force: bool,
+        dir: PathBuf,
+    },
+}
+
+fn word_set(
+    path: &Path,
+    language: &Language,
+) -> Result<BTreeSet<String>, Box<dyn std::error::Error>> {
+    Ok(words::read_lines(path, language)?
+        .into_iter()
+        .map(|(_, word)| word)
+        .collect())
+}
+
+fn write_list(path: Option<&Path>, list: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut text = list.join("\n");
+    text.push('\n');
+    match path {
+        Some(x) => {
+            std::fs::write(x, text).map_err(|e| format!("cannot write {}: {}", x.display(), e))?
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+pub fn run(
+    command: &ListsCommand,
+    final_repo: Option<&Path>,         //-f
+    accept_repo: Option<&Path>,        //-a
+    custom: bool,                      //--custom-lists
+    language: &Language,               //--lang
+    frequencies: Option<&Frequencies>, //--freq
+) -> Result<(), Box<dyn std::error::Error>> {
+    //pack words are checked against the pack's alphabet, never the english lists
+    let custom = custom || *language != Language::english();
+    match command {
+        ListsCommand::Validate { file, set } => {
+            let entries = words::read_lines(file, language)?;
+            let issues = words::check(&entries, (!custom).then(|| set.builtin()), language);
+            for x in issues.iter() {
+                println!("{}:{}: {}: {}", file.display(), x.line, x.word, x.problem);
+            }
+            if entries.is_empty() {
+                return Err(format!("{}: no words", file.display()).into());
+            }
+            if !issues.is_empty() {
+                return Err(format!("{} problems in {} words", issues.len(), entries.len()).into());
+            }
+            println!("{}: {} words, ok", file.display(), entries.len());
+        }
+        ListsCommand::Diff { a, b } => {
+            let (a_words, b_words) = (word_set(a, language)?, word_set(b, language)?);
+            for word in a_words.difference(&b_words) {
+                println!("- {}", word);
+            }
+            for word in b_words.difference(&a_words) {
+                println!("+ {}", word);
+            }
+            eprintln!(
+                "{} only in {}, {} only in {}, {} in both",
+                a_words.difference(&b_words).count(),
+                a.display(),
+                b_words.difference(&a_words).count(),
+                b.display(),
+                a_words.intersection(&b_words).count()
+            );
+        }
+        ListsCommand::Merge { files, output } => {
+            let mut merged = BTreeSet::new();
+            for file in files.iter() {
+                merged.extend(word_set(file, language)?);
+            }
+            let list: Vec<&str> = merged.iter().map(|x| x.as_str()).collect();
+            write_list(output.as_deref(), &list)?;
+        }
+        ListsCommand::Filter {
+            file,
+            min,
+            top,
+            output,
+        } => {
+            let Some(frequencies) = frequencies else {
+                return Err(
+                    String::from("lists filter needs a frequency table, use --freq").into(),
+                );
+            };
+            let all = word_set(file, language)?;
+            let total = all.len();
+            let mut kept: Vec<String> = all
+                .into_iter()
+                .filter(|x| frequencies.weight(x) >= *min)
+                .collect();
+            if let Some(n) = top {
+                //most common first, ties in letter order
+                kept.sort_by(|a, b| frequencies.weight(b).total_cmp(&frequencies.weight(a)));
+                kept.truncate(*n);
+                kept.sort();
+            }
+            let list: Vec<&str> = kept.iter().map(|x| x.as_str()).collect();
+            write_list(output.as_deref(), &list)?;
+            eprintln!("{} of {} words kept", list.len(), total);
+        }
+        ListsCommand::Export {
+            builtin,
+            force,
+            dir,
+        } => {
+            //checked before anything is written, so a refusal leaves DIR as it was
+            let names = ["final.txt", "acceptable.txt"];
+            if !force && let Some(x) = names.iter().map(|x| dir.join(x)).find(|x| x.exists()) {
+                return Err(
+                    format!("{} already exists, --force to replace it", x.display()).into(),
+                );
+            }
+            //the lists as the game loads them
+            let english = Language::english();
+            let (final_list, accept_list) = words::load_lists(
+                final_repo.filter(|_| !builtin),
+                accept_repo.filter(|_| !builtin),
+                custom,
+                if *builtin { &english } else { language },
+            )?;
+            std::fs::create_dir_all(dir)?;
+            let accept_list: Vec<String> = accept_list.words().collect();
+            for (name, list) in names.into_iter().zip([&final_list, &accept_list]) {
+                let list: Vec<&str> = list.iter().map(|x| x.as_str()).collect();
+                write_list(Some(&dir.join(name)), &list)?;
+            }
+            println!(
+                "{} and {} words written to {}",
+                final_list.len(),
+                accept_list.len(),
+                dir.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_path;
+
+    fn entries(words: &[&str]) -> Vec<(usize, String)> {
+        words
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (i + 1, x.to_string()))
+            .collect()
+    }
+
+    fn validate(text: &str, name: &str, custom: bool) -> Result<(), String> {
+        let path = crate::testutil::temp_file(name, text);
+        let command = ListsCommand::Validate {
+            file: path.clone(),
+            set: Set::Final,
+        };
+        let result = run(&command, None, None, custom, &Language::english(), None);
+        std::fs::remove_file(path).unwrap();
+        result.map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn check_reports_every_problem() {
+        let english = Language::english();
+        let issues = words::check(
+            &entries(&["crane", "cr4ne", "cran", "crane", "zzzzz"]),
+            Some(FINAL),
+            &english,
+        );
+        let found: Vec<(usize, &str)> = issues
+            .iter()
+            .map(|x| (x.line, x.problem.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, "not alphabetic"),
+                (2, "not in the builtin list"),
+                (3, "4 letters, expect 5"),
+                (3, "not in the builtin list"),
+                (4, "duplicate of line 1"),
+                (5, "not in the builtin list"),
+            ]
+        );
+        //custom lists only keep the shape rules
+        assert!(words::check(&entries(&["zzzzz", "qqqqq"]), None, &english).is_empty());
+    }
+
+    #[test]
+    fn validate_fails_on_problems() {
+        assert_eq!(validate("crane\n\nSLATE\n", "ok.txt", false), Ok(()));
+        assert_eq!(
+            validate("crane\nzzzzz\ncrane\n", "bad.txt", false).unwrap_err(),
+            "2 problems in 3 words"
+        );
+        assert_eq!(validate("zzzzz\n", "custom.txt", true), Ok(()));
+        assert!(validate("\n\n", "empty.txt", false).is_err());
+    }
+
+    #[test]
+    fn export_keeps_existing_files() {
+        let dir = temp_path("export");
+        let export = |force: bool| {
+            let command = ListsCommand::Export {
+                builtin: true,
+                force,
+                dir: dir.clone(),
+            };
+            run(&command, None, None, false, &Language::english(), None)
+        };
+        export(false).unwrap();
+        let written = std::fs::read_to_string(dir.join("final.txt")).unwrap();
+        assert_eq!(written.lines().count(), FINAL.len());
+        std::fs::write(dir.join("final.txt"), "mine\n").unwrap();
+        assert!(export(false).is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("final.txt")).unwrap(),
+            "mine\n"
+        );
+        export(true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("final.txt")).unwrap(),
+            written
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
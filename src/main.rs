@@ -1,29 +1,45 @@
 use colored::Colorize;
 use rand::SeedableRng;
-use rand::seq::SliceRandom;
-use std::char;
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::fs::File;
 use std::io::{self, Write};
 mod builtin_words;
-use builtin_words::ACCEPTABLE;
-use builtin_words::FINAL;
-use clap::Parser;
+mod duel;
+mod engine;
+mod events;
+mod freq;
+mod lang;
+mod leaderboard;
+mod lists;
+mod race;
+mod referee;
+mod server;
+mod store;
+mod survival;
+#[cfg(test)]
+mod testutil;
+mod theme;
+mod tips;
+mod tui;
+mod words;
+use clap::{Parser, Subcommand};
 use config::Config;
+use engine::{Round, clock};
+use events::Event;
 use rand::Rng;
 use rand::rngs::StdRng;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::collections::BTreeMap;
-use std::io::BufReader;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use store::{Game, JsonState, StateStore};
+use words::WordSet;
 
 //help print colorful chracters
-fn pr(c: char) {
+fn pr(c: char, palette: &theme::Palette) {
     match &c {
-        'R' => print!("{}", "R".bold().red()),
-        'Y' => print!("{}", "Y".bold().yellow()),
-        'G' => print!("{}", "G".bold().green()),
+        'R' | 'Y' | 'G' => {
+            let theme::Rgb(r, g, b) = palette.color(c);
+            print!("{}", c.to_string().bold().truecolor(r, g, b))
+        }
         'X' => print!("{}", "X".bold()),
         _ => print!("invaild"),
     }
@@ -49,12 +65,128 @@ struct Cli {
     final_repo: Option<PathBuf>,
     #[arg(short = 'a', long = "acceptable-set")]
     accept_repo: Option<PathBuf>,
+    #[arg(long = "custom-lists")]
+    // -f/-a need not be subsets of the builtin lists, only final of acceptable
+    custom_lists: bool,
+    #[arg(long = "lang")]
+    // language pack file (json), english when not given, langs/samples has small demo packs
+    lang: Option<PathBuf>,
+    #[arg(long = "freq")] // word frequency table, common answers come up more often
+    freq: Option<PathBuf>,
     #[arg(short = 'S', long = "state")]
     state: Option<PathBuf>,
     #[arg(short = 'c', long = "config")]
     config: Option<PathBuf>,
     #[arg(short = 'p', long = "tips")] // -p to give recommand list
     tips: bool,
+    #[arg(short = 'P', long = "player")] // profile name, skips the name prompt
+    player: Option<String>,
+    #[arg(long = "new-cycle")] // -r: answers this player already had (-S) may come again
+    new_cycle: bool,
+    #[arg(long = "tui")] // full-screen terminal ui with on-screen keyboard
+    tui: bool,
+    #[arg(long = "accessible")] // plain sentences instead of colored letters, for screen readers
+    accessible: bool,
+    #[arg(long = "format", value_enum, default_value = "legacy")]
+    // non-tty output, jsonl for tools and bots
+    format: events::Format,
+    #[arg(long = "join")] // ws://host:port/room of a `race` host, played in the tui
+    join: Option<String>,
+    #[arg(long = "theme")] // dark, light, high-contrast or a palette from the config
+    theme: Option<String>,
+    #[arg(long = "time-limit")] // seconds per game, the game is lost when they run out
+    time_limit: Option<u64>,
+    #[arg(skip)] // resolved from --theme by merge_config
+    palette: theme::Palette,
+    #[arg(skip)] // loaded from --lang by merge_config
+    language: lang::Language,
+    #[arg(skip)] // -s, or drawn by merge_config, every random choice of the session comes from it
+    session_seed: u64,
+    #[arg(skip)] // loaded from --freq by merge_config
+    frequencies: Option<freq::Frequencies>,
+    #[arg(skip)] // recorded mode of the games of a subcommand (duel, speedrun, survival)
+    mode: Option<String>,
+    #[arg(skip)] // guesses for one game of a survival run
+    max_turns: Option<usize>,
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+//state file maintenance, the backend is chosen by extension (.db/.sqlite -> sqlite)
+#[derive(Subcommand, Clone)]
+enum Commands {
+    /// Copy the games of a json state file into a sqlite database
+    Import {
+        json: PathBuf,
+        db: PathBuf,
+        #[arg(long = "force")] // add to an existing database, games already in it are skipped
+        force: bool,
+    },
+    /// Dump a sqlite database into a json state file
+    Export {
+        db: PathBuf,
+        json: PathBuf,
+        #[arg(long = "force")] // add to an existing json file, games already in it are skipped
+        force: bool,
+    },
+    /// Play seeded games from the final set against external solvers and print a scoreboard
+    Referee {
+        #[arg(long = "bot", required = true)] // solver command line, repeat for a competition
+        bots: Vec<String>,
+        #[arg(short = 'n', long = "games", default_value_t = 10)]
+        games: usize,
+        #[arg(long = "timeout", default_value_t = 10)] // seconds a bot may think per message
+        timeout: u64,
+        #[arg(long = "format", value_enum, default_value = "table")]
+        format: leaderboard::OutputFormat,
+    },
+    /// Serve a json api on localhost, finished games go to the state file (-S)
+    Serve {
+        #[arg(long = "port", default_value_t = 8080)]
+        port: u16,
+        #[arg(long = "ttl", default_value_t = 1800)] // seconds an idle game is kept
+        ttl: u64,
+    },
+    /// Host race rooms on localhost, everyone in a room gets the same seeded answer (-s, -d)
+    Race {
+        #[arg(long = "port", default_value_t = 8090)]
+        port: u16,
+    },
+    /// Two players at one terminal take turns setting a hidden word and guessing it
+    Duel {
+        #[arg(num_args = 2, required = true)] // the first one sets the first word
+        players: Vec<String>,
+        #[arg(short = 'n', long = "rounds", default_value_t = 2)]
+        rounds: usize,
+        #[arg(long = "any-word")]
+        // allow any acceptable word as the answer, not just the final set
+        any_word: bool,
+    },
+    /// Play seeded puzzles (-s, -d) back to back against the clock
+    Speedrun {
+        #[arg(short = 'n', long = "puzzles", default_value_t = 5)]
+        puzzles: usize,
+    },
+    /// Endless run of seeded words (-s, -d) on a budget of 12 guesses, solved words give back the unused ones
+    Survival,
+    /// Check, compare, merge and export word list files
+    Lists {
+        #[command(subcommand)]
+        command: lists::ListsCommand,
+    },
+    /// Rank the players of the state file (-S), best runs with --mode survival
+    Leaderboard {
+        #[arg(short = 'm', long = "metric", value_enum, default_value = "score")]
+        metric: leaderboard::Metric,
+        #[arg(long = "from")] // YYYY-MM-DD, inclusive
+        from: Option<String>,
+        #[arg(long = "to")] // YYYY-MM-DD, inclusive
+        to: Option<String>,
+        #[arg(long = "mode", value_parser = ["normal", "difficult", "daily", "race", "duel", "speedrun", "survival"])]
+        mode: Option<String>,
+        #[arg(long = "format", value_enum, default_value = "table")]
+        format: leaderboard::OutputFormat,
+    },
 }
 
 //for reactive mood,output the guess result history
@@ -63,20 +195,6 @@ struct GameHistory {
     char_status_history: Vec<char>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct JsonState {
-    #[serde(default)] //default: allow {} empty json file
-    total_rounds: i32,
-    #[serde(default)]
-    games: Vec<Game>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Game {
-    answer: String,
-    guesses: Vec<String>,
-}
-
 //parse config file
 #[derive(Debug, Deserialize, Default)]
 struct AppConfig {
@@ -89,12 +207,23 @@ struct AppConfig {
     acceptable_set: Option<PathBuf>,
     state: Option<PathBuf>,
     word: Option<String>,
+    player: Option<String>,
+    accessible: Option<bool>,
+    theme: Option<String>,
+    themes: Option<BTreeMap<String, theme::Palette>>, //custom palettes by name
+    time_limit: Option<u64>,
+    custom_lists: Option<bool>,
+    lang: Option<PathBuf>,
+    freq: Option<PathBuf>,
 }
 
+const DEFAULT_SEED: u64 = 42; //-d without -s, so a day is the same word for everyone
+
 fn merge_config(cli: &Cli) -> Result<Cli, Box<dyn std::error::Error>> {
     // merge config.json and commond line arguments
 
     let mut merged_cli = cli.clone();
+    let mut palettes = BTreeMap::new();
 
     if let Some(config_path) = &cli.config {
         let settings = Config::builder()
@@ -130,304 +259,311 @@ fn merge_config(cli: &Cli) -> Result<Cli, Box<dyn std::error::Error>> {
         if merged_cli.state.is_none() {
             merged_cli.state = app_config.state;
         }
+        if merged_cli.player.is_none() {
+            merged_cli.player = app_config.player;
+        }
+        if !merged_cli.accessible {
+            merged_cli.accessible = app_config.accessible.unwrap_or(false);
+        }
+        if merged_cli.theme.is_none() {
+            merged_cli.theme = app_config.theme;
+        }
+        if merged_cli.time_limit.is_none() {
+            merged_cli.time_limit = app_config.time_limit;
+        }
+        if !merged_cli.custom_lists {
+            merged_cli.custom_lists = app_config.custom_lists.unwrap_or(false);
+        }
+        if merged_cli.lang.is_none() {
+            merged_cli.lang = app_config.lang;
+        }
+        if merged_cli.freq.is_none() {
+            merged_cli.freq = app_config.freq;
+        }
+        palettes = app_config.themes.unwrap_or_default();
     }
+    merged_cli.palette = theme::resolve(merged_cli.theme.as_deref().unwrap_or("dark"), &palettes)?;
+    if let Some(ref x) = merged_cli.lang {
+        merged_cli.language = lang::Language::load(x)?;
+    }
+    if let Some(ref x) = merged_cli.freq {
+        merged_cli.frequencies = Some(freq::Frequencies::load(x, &merged_cli.language)?);
+    }
+    merged_cli.session_seed = match merged_cli.seed {
+        Some(x) => x,
+        None if merged_cli.days != 1 => DEFAULT_SEED,
+        None => rand::thread_rng().r#gen(),
+    };
 
     Ok(merged_cli)
 }
 
-fn play_tty(
-    //reactive mood
-    cli: &Cli,                              //command line arguments
-    answer_list: &mut Vec<String>,          //avoid repeated answers
-    guess_list: &mut BTreeMap<String, i32>, //count the number of guesses
-    final_list: &[String],                  //given answer reposity
-    accept_list: &[String],                 //guess reposity
-    json_data: &mut JsonState,              //write state json
-    id: usize,                              //days use in -r mood
-) -> i32 {
-    let mut game: Game = Game {
-        answer: String::new(),
-        guesses: Vec::new(),
-    };
-    let mut game_record: Vec<GameHistory> = Vec::new();
-
-    if cli.words.is_some() {
-        if cli.days != 1 {
-            return 10000;
-        }
-        if cli.seed.is_some() {
-            return 10000;
-        }
-    }
-    if cli.rand_verbos && cli.words.is_some() {
-        return 10000;
+fn game_mode(cli: &Cli) -> String {
+    //recorded with each game, used by the leaderboard filter
+    if let Some(ref x) = cli.mode {
+        x.clone()
+    } else if cli.diff_verbos {
+        "difficult".to_string()
+    } else if cli.rand_verbos && cli.days != 1 {
+        "daily".to_string()
+    } else {
+        "normal".to_string()
     }
-    //avoid mood conflict
+}
 
-    println!(
-        "I am in a tty. Please print {}!",
-        "colorful characters".bold().red()
-    );
-    print!("{}", "Your name: ".bold().blue());
-    io::stdout().flush().unwrap();
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).expect("cannot read");
-    println!("Welcome to wordle, {}!", line.trim());
-    io::stdout().flush().unwrap();
+//one round of the game, returns guesses used, 0 failed, 10000 mood mix
+type PlayFn = fn(
+    &Cli,
+    &mut Vec<String>,
+    &mut BTreeMap<String, i32>,
+    &[String],
+    &WordSet,
+    &mut JsonState,
+    usize,
+) -> i32;
 
+fn mood_mix(cli: &Cli) -> bool {
+    //avoid mood conflict
+    if cli.words.is_some() && (cli.days != 1 || cli.seed.is_some()) {
+        return true;
+    }
+    cli.rand_verbos && cli.words.is_some()
+}
+
+fn choose_answer(
+    cli: &Cli,
+    answer_list: &mut Vec<String>, //avoid repeated answers
+    final_list: &[String],
+    id: usize,
+    prompt: bool, //ask for the word when reading it from stdin
+) -> String {
     // word-given mood switch
     let mut answer_word: String;
-
     if cli.rand_verbos {
-        if cli.days == 1 {
-            //not give -d
-            let rand_index = rand::thread_rng().gen_range(0..FINAL.len());
-            answer_word = FINAL[rand_index].to_string();
-            while answer_list.contains(&answer_word.clone()) {
-                //conflict , generate again
-                let rand_index = rand::thread_rng().gen_range(0..FINAL.len());
-                answer_word = FINAL[rand_index].to_string();
+        //the list shuffled by the session seed, walked from -d past the answers of this cycle
+        let len = final_list.len();
+        answer_word = match (0..len)
+            .map(|i| &final_list[(id + i) % len])
+            .find(|x| !answer_list.contains(x))
+        {
+            Some(x) => x.clone(),
+            None => {
+                //every word came up, start over from the same place
+                answer_list.clear();
+                final_list[id % len].clone()
             }
-        } else {
-            answer_word = final_list[id % final_list.len()].to_string() //use user given reposity
         }
     } else if let Some(x) = &cli.words {
-        answer_word = x.clone(); //given answer words in command line
+        answer_word = cli.language.normalize(x); //given answer words in command line
+    } else if prompt {
+        //typed by someone else at the same terminal
+        answer_word = match ask_answer("Input the answer word:", final_list, None, &cli.language) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
     } else {
-        print!("Input the answer word:");
-        io::stdout().flush().unwrap();
         answer_word = String::new();
         io::stdin()
             .read_line(&mut answer_word)
             .expect("cannot read");
-        //io::stdout().flush().unwrap();
+        answer_word = cli.language.normalize(&answer_word); //drop the newline
     }
     answer_list.push(answer_word.clone());
-    game.answer = answer_word.to_uppercase().clone(); //store game status
-
-    let mut chracter_status: Vec<char> = ['X'; 26].to_vec(); //total status for 26 characters
-    let answer_word_vector: Vec<char> = answer_word.chars().collect(); //transform str into list
-    let mut appearance: HashMap<char, i32> = HashMap::new(); // each chracter's number in answer word
-    for c in answer_word.chars() {
-        let pos = appearance.entry(c).or_insert(0);
-        *pos += 1;
-    }
+    answer_word
+}
 
-    let mut guess = String::new();
-    let mut turn = 1; //<=6 rounds
-    let mut flag: bool = false; //success guess!
-    let mut green_list: Vec<(char, i32)> = Vec::new(); //green state letters, use in difficult mood and tips mood
-    let mut yellow_list: Vec<char> = Vec::new(); //yellow state letters
-    let mut red_list: Vec<char> = Vec::new();
-
-    while turn <= 6 {
-        println!("You have {} chance left,Input you guess:", 7 - turn);
-        io::stdin().read_line(&mut guess).expect("cannot read");
-        guess = guess.to_lowercase(); //convenient for vertify
-
-        let mut guess_word_vector: Vec<char> = guess.chars().collect(); //transform str into list
-        let mut guess_appearance = appearance.clone(); // required number in guess word,for vertify overplus 
-        let mut s_status: Vec<char> = ['R'; 5].to_vec(); //// each chracter's status in guess word,default='R'
-        let mut input_flag: bool = true; //legal input
-
-        if !(accept_list.contains(&guess.trim().to_string())) {
-            //not  in ACCEPTABLE
-            input_flag = false;
-        } else if guess.len() != 6 {
-            //length illegal
-            input_flag = false;
-        } else if cli.diff_verbos {
-            // difficult mood : don't accord with previous answer
-            for iter in green_list.iter() {
-                if guess_word_vector[iter.1 as usize] != iter.0 {
-                    input_flag = false;
-                    break;
-                }
-            }
-            for iter in yellow_list.iter() {
-                if !guess.contains(*iter) {
-                    input_flag = false;
-                    break;
-                }
-            }
+fn ask_answer(
+    prompt: &str,
+    final_list: &[String],
+    accept_list: Option<&WordSet>, //also allow any guessable word
+    language: &lang::Language,
+) -> io::Result<String> {
+    //masked entry, asked again until it's a word of the lists
+    loop {
+        let word = if atty::is(atty::Stream::Stdin) {
+            tui::read_secret(prompt)?
         } else {
-            for i in guess_word_vector.iter().take(5) {
-                //chracter
-                if !((*i >= 'A' && *i <= 'Z') || (*i >= 'a' && *i <= 'z')) {
-                    input_flag = false;
-                    break;
-                }
+            //piped in, nobody to hide it from
+            print!("{}", prompt);
+            io::stdout().flush()?;
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
             }
-        }
-        if !input_flag {
-            println!("INVALID");
-        }
+            line
+        };
+        let word = language.normalize(&word);
+        let listed = final_list.contains(&word) || accept_list.is_some_and(|x| x.contains(&word));
+        let error = if lang::graphemes(&word).len() != engine::WORD_LEN {
+            engine::GuessError::Length
+        } else if !word.chars().all(|c| language.is_letter(c)) {
+            engine::GuessError::NotAlphabetic
+        } else if !listed {
+            engine::GuessError::NotInList
+        } else {
+            return Ok(word);
+        };
+        println!("{}, try again.", error.message());
+    }
+}
 
-        if input_flag {
-            *guess_list
-                .entry(guess.trim().to_string().to_uppercase())
-                .or_insert(0) += 1; //count guess frequency
-            game.guesses.push(guess.trim().to_uppercase().to_string()); //update game state
+fn new_game(cli: &Cli, answer_word: &str) -> Game {
+    Game {
+        answer: answer_word.trim().to_uppercase(),
+        guesses: Vec::new(),
+        player: cli.player.clone(),
+        played_at: Some(store::now()),
+        mode: Some(game_mode(cli)),
+        opponent: None,
+        times: Vec::new(),
+    }
+}
 
-            for i in 0..5 {
-                if guess_word_vector[i] == answer_word_vector[i] {
-                    s_status[i] = 'G';
-                    *guess_appearance.entry(answer_word_vector[i]).or_insert(0) -= 1;
-                } //give 'G'
-            }
-            for i in 0..5 {
-                if guess_word_vector[i] != answer_word_vector[i]
-                    && let Some(&x) = guess_appearance.get(&guess_word_vector[i])
-                    && x > 0
-                {
-                    //don't overplus
-                    s_status[i] = 'Y';
-                    *guess_appearance.entry(guess_word_vector[i]).or_insert(0) -= 1; // when reach 0, the number of letter equals,the rest need to be red
-                }
+fn new_round(cli: &Cli, answer_word: &str) -> Round {
+    let mut round = Round::with_alphabet(answer_word, cli.diff_verbos, cli.language.letters());
+    if let Some(x) = cli.max_turns {
+        round.max_turns = x;
+    }
+    round
+}
 
-                match s_status[i] {
-                    //renew the character's status
-                    'G' => {
-                        green_list.push((guess_word_vector[i], i as i32));
-                        chracter_status[((guess_word_vector[i] as u8) - b'a') as usize] = 'G'
-                    }
-                    'Y' if chracter_status[((guess_word_vector[i] as u8) - b'a') as usize]
-                        != 'G' =>
-                    {
-                        yellow_list.push(guess_word_vector[i]);
-                        chracter_status[((guess_word_vector[i] as u8) - b'a') as usize] = 'Y'
-                    }
-                    'R' if (chracter_status[((guess_word_vector[i] as u8) - b'a') as usize]
-                        != 'G'
-                        && chracter_status[((guess_word_vector[i] as u8) - b'a') as usize]
-                            != 'Y') =>
-                    {
-                        red_list.push(guess_word_vector[i]);
-                        chracter_status[((guess_word_vector[i] as u8) - b'a') as usize] = 'R';
-                    }
-                    _ => continue,
-                }
+fn play_tty(
+    //reactive mood
+    cli: &Cli,                              //command line arguments
+    answer_list: &mut Vec<String>,          //avoid repeated answers
+    guess_list: &mut BTreeMap<String, i32>, //count the number of guesses
+    final_list: &[String],                  //given answer reposity
+    accept_list: &WordSet,                  //guess reposity
+    json_data: &mut JsonState,              //write state json
+    id: usize,                              //days use in -r mood
+) -> i32 {
+    if mood_mix(cli) {
+        return 10000;
+    }
+    let answer_word = choose_answer(cli, answer_list, final_list, id, true);
+    let mut game = new_game(cli, &answer_word); //store game status
+    let mut round = new_round(cli, &answer_word);
+    let mut game_record: Vec<GameHistory> = Vec::new();
+    let started = Instant::now();
+    let limit = cli.time_limit.map(Duration::from_secs);
+
+    let mut guess = String::new();
+    while !round.finished() {
+        let elapsed = started.elapsed();
+        let timer = match limit {
+            Some(x) => format!("{} left", clock(x.saturating_sub(elapsed).as_secs())),
+            None => clock(elapsed.as_secs()),
+        };
+        println!(
+            "[{}] You have {} chance left,Input you guess:",
+            timer,
+            round.max_turns - round.guesses.len()
+        );
+        guess.clear();
+        if io::stdin().read_line(&mut guess).expect("cannot read") == 0 {
+            break; //stdin closed
+        }
+        if limit.is_some_and(|x| started.elapsed() > x) {
+            println!("Time's up!");
+            break;
+        }
+        let word = cli.language.normalize(&guess); //convenient for vertify
+        if let Err(e) = round.submit(&word, accept_list) {
+            if cli.accessible {
+                println!("Invalid guess: {}.", e.message());
+            } else {
+                println!("INVALID");
             }
+            continue;
+        }
+        *guess_list.entry(word.to_uppercase()).or_insert(0) += 1; //count guess frequency
+        game.guesses.push(word.to_uppercase()); //update game state
+        game.times.push(started.elapsed().as_millis() as u64);
 
-            game_record.push(GameHistory {
-                s_status_history: s_status.clone(),
-                char_status_history: chracter_status.clone(),
-            }); //update game record
+        game_record.push(GameHistory {
+            s_status_history: round.feedback.last().unwrap().clone(),
+            char_status_history: round.chracter_status.clone(),
+        }); //update game record
 
+        if cli.accessible {
+            print_accessible(&round);
+        } else {
             for history_iter in game_record.iter() {
-                flag = true;
                 for iter in history_iter.s_status_history.iter() {
-                    pr(*iter);
-                    if *iter != 'G' {
-                        flag = false;
-                    }
+                    pr(*iter, &cli.palette);
                 }
                 print!(" ");
                 for iter in history_iter.char_status_history.iter() {
-                    pr(*iter);
+                    pr(*iter, &cli.palette);
                 }
                 println!();
             } //print historic info
-
-            if cli.tips && turn != 6 {
-                let mut pos_flag: bool;
-                let mut pos_word_list: Vec<&str> = Vec::new();
-                for &possible_word in ACCEPTABLE {
-                    //visit all the words and judge if it correspond with previous answers
-                    pos_flag = true;
-                    let pos_word_vec: Vec<char> = possible_word.chars().collect();
-                    for iter in green_list.iter() {
-                        if pos_word_vec[iter.1 as usize] != iter.0 {
-                            pos_flag = false;
-                            break;
-                        }
-                    }
-                    for iter in yellow_list.iter() {
-                        if !possible_word.contains(*iter) || !pos_flag {
-                            pos_flag = false;
-                            break;
-                        }
-                    }
-                    for iter in red_list.iter() {
-                        if possible_word.contains(*iter) || !pos_flag {
-                            pos_flag = false;
-                            break;
-                        }
-                    }
-                    if pos_flag {
-                        pos_word_list.push(possible_word);
-                    }
-                }
-                calculate_entropy(&mut pos_word_list); //use entropy to give recommand list
-                println!("{:?}", pos_word_list);
-            }
-        }
-        if input_flag {
-            turn += 1;
-        }
-        if flag {
-            break;
         }
 
-        s_status.clear();
-        guess.clear();
-        guess_appearance.clear();
-        guess_word_vector.clear();
+        if cli.tips && !round.finished() {
+            let pos_word_list = round.candidates(accept_list);
+            calculate_entropy(&pos_word_list, accept_list, cli.frequencies.as_ref()); //use entropy to give recommand list
+            let words: Vec<String> = pos_word_list
+                .iter()
+                .map(|x| accept_list.unpack(x))
+                .collect();
+            println!("{:?}", words);
+        }
     }
+    let seconds = game.seconds();
     json_data.games.push(game);
-    turn -= 1;
-    println!("Guess turns:{}", turn);
-    if !flag {
+    if !round.guesses.is_empty() {
+        //emoji grid to paste elsewhere, no letters given away
+        print!(
+            "{}",
+            theme::share_grid(&round.feedback, round.won(), round.max_turns, &cli.palette)
+        );
+    }
+    println!("Guess turns:{}", round.guesses.len());
+    if round.won()
+        && let Some(x) = seconds
+    {
+        println!("Time:{}", clock(x as u64));
+    }
+    if !round.won() {
         println!("Answer:{}", answer_word.to_uppercase());
         return 0;
     }
-    turn
+    round.guesses.len() as i32
 }
 
-use ordered_float::OrderedFloat;
-use priority_queue::PriorityQueue;
+fn print_accessible(round: &Round) {
+    //one sentence per hint, the letter status grouped by category
+    let row = round.guesses.len() - 1;
+    println!(
+        "Guess {}: {}.",
+        round.guesses.len(),
+        round.describe_guess(row)
+    );
+    println!("{}", round.describe_alphabet());
+}
 
-fn calculate_entropy(pos_word_list: &mut Vec<&str>) {
+fn calculate_entropy(
+    pos_word_list: &[words::Packed],
+    accept_list: &WordSet,
+    frequencies: Option<&freq::Frequencies>, //prior, likely answers first
+) {
     //use entropy to give recommand list
-    let len = pos_word_list.len();
-    let mut recommond_list = PriorityQueue::new();
-    for i in pos_word_list.iter() {
-        let mut entropy = 0.0;
-        let mut possible_analyse: [i32; 300] = [0; 300];
-        for j in pos_word_list.iter() {
-            //visit all the possible answers
-            let mut pos_num = 0; //use ternary to condense state
-            for pos in 0..5 {
-                if i.chars().nth(pos) == j.chars().nth(pos) {
-                    //'G'
-                    pos_num *= 3;
-                    continue;
-                }
-                if let Some(target_char) = j.chars().nth(pos)
-                    && i.find(target_char).is_some()
-                {
-                    //'Y'
-                    pos_num = pos_num * 3 + 1;
-                    continue;
-                }
-                pos_num = pos_num * 3 + 2; //'R'
-            }
-            possible_analyse[pos_num] += 1;
-        }
-        for k in possible_analyse.iter_mut().take(244) {
-            if *k > 0 {
-                let p = *k as f64 / len as f64;
-                entropy += -p * f64::log2(p);
-            }
-            *k = 0;
-        }
-        recommond_list.push(*i, OrderedFloat(entropy)); //orderflofat : can compare f64
-    }
+    let prior: Option<Vec<f64>> = frequencies.map(|f| {
+        pos_word_list
+            .iter()
+            .map(|x| f.weight(&accept_list.unpack(x)))
+            .collect()
+    });
     println!("Top 5 words by entropy:");
-    for _ in 0..5 {
-        if let Some((word, entropy)) = recommond_list.pop() {
-            println!("{}: {:.4}", word, entropy.0);
-        }
+    for (word, entropy) in tips::entropy_ranking(pos_word_list, prior.as_deref())
+        .iter()
+        .take(5)
+    {
+        println!("{}: {:.4}", accept_list.unpack(word), entropy);
     }
 }
 
@@ -437,490 +573,726 @@ fn play_dis_tty(
     answer_list: &mut Vec<String>,
     guess_list: &mut BTreeMap<String, i32>,
     final_list: &[String],
-    accept_list: &[String],
+    accept_list: &WordSet,
     json_data: &mut JsonState,
     id: usize,
 ) -> i32 {
-    let mut game: Game = Game {
-        answer: String::new(),
-        guesses: Vec::new(),
-    };
-    let mut answer_word: String;
-    if cli.words.is_some() {
-        if cli.days != 1 {
-            return 10000;
-        }
-        if cli.seed.is_some() {
-            return 10000;
-        }
-    }
-    if cli.rand_verbos && cli.words.is_some() {
+    if mood_mix(cli) {
         return 10000;
     }
-    if cli.rand_verbos {
-        if cli.days == 1 {
-            let rand_index = rand::thread_rng().gen_range(0..FINAL.len());
-            answer_word = FINAL[rand_index].to_string();
-            while answer_list.contains(&answer_word.clone()) {
-                let rand_index = rand::thread_rng().gen_range(0..FINAL.len());
-                answer_word = FINAL[rand_index].to_string();
-            }
-        } else {
-            //println!("{} {} {:?}",final_list.len(),id,final_list.iter().position(|&x| x == "photo"));
-            answer_word = final_list[id % final_list.len()].to_string()
-        }
-    } else if let Some(x) = &cli.words {
-        answer_word = x.clone();
-    } else {
-        answer_word = String::new();
-        io::stdin()
-            .read_line(&mut answer_word)
-            .expect("cannot read");
-        //io::stdout().flush().unwrap();
+    let answer_word = choose_answer(cli, answer_list, final_list, id, false);
+    let mut game = new_game(cli, &answer_word);
+    let mut round = new_round(cli, &answer_word);
+    let jsonl = cli.format == events::Format::Jsonl;
+    if jsonl {
+        Event::game_start(
+            json_data.games.len() + 1,
+            game_mode(cli),
+            cli.player.clone(),
+            round.max_turns,
+        )
+        .emit();
     }
-    answer_list.push(answer_word.clone());
-    game.answer = answer_word.to_uppercase().clone();
     io::stdout().flush().unwrap();
-
-    let mut chracter_status: Vec<char> = ['X'; 26].to_vec();
-    let answer_word_vector: Vec<char> = answer_word.chars().collect();
-    let mut appearance: HashMap<char, i32> = HashMap::new();
-    for c in answer_word.chars() {
-        let pos = appearance.entry(c).or_insert(0);
-        *pos += 1;
-    }
+    let started = Instant::now();
+    let limit = cli.time_limit.map(Duration::from_secs);
 
     let mut guess = String::new();
-    let mut turn = 1;
-    let mut flag: bool = false;
-    let mut green_list: Vec<(char, i32)> = Vec::new();
-    let mut yellow_list: Vec<char> = Vec::new();
-
-    while turn <= 6 {
-        io::stdin().read_line(&mut guess).expect("cannot read");
-
-        let mut guess_word_vector: Vec<char> = guess.chars().collect();
-        let mut guess_appearance = appearance.clone();
-        let mut s_status: Vec<char> = ['R'; 5].to_vec();
-        let mut input_flag: bool = true;
-
-        if !(accept_list.contains(&guess.trim().to_string())) {
-            //not  in ACCEPTABLE
-            input_flag = false;
-        } else if guess.len() != 6 {
-            input_flag = false;
-        } else if cli.diff_verbos {
-            for iter in green_list.iter() {
-                if guess_word_vector[iter.1 as usize] != iter.0 {
-                    input_flag = false;
-                    break;
-                }
-            }
-            for iter in yellow_list.iter() {
-                if !guess.contains(*iter) {
-                    input_flag = false;
-                    break;
-                }
-            }
-        } else {
-            for i in guess_word_vector.iter().take(5) {
-                //chracter
-                if !((*i >= 'A' && *i <= 'Z') || (*i >= 'a' && *i <= 'z')) {
-                    input_flag = false;
-                    break;
-                }
-            }
+    while !round.finished() {
+        guess.clear();
+        if io::stdin().read_line(&mut guess).expect("cannot read") == 0 {
+            break; //stdin closed
         }
-        if !input_flag {
-            println!("INVALID");
+        if limit.is_some_and(|x| started.elapsed() > x) {
+            break; //too late, lost
         }
-
-        if input_flag {
-            *guess_list
-                .entry(guess.trim().to_uppercase().clone())
-                .or_insert(0) += 1;
-            game.guesses.push(guess.trim().to_uppercase().to_string());
-
-            for i in 0..5 {
-                if guess_word_vector[i] == answer_word_vector[i] {
-                    s_status[i] = 'G';
-                    *guess_appearance.entry(answer_word_vector[i]).or_insert(0) -= 1;
-                }
-            }
-            for i in 0..5 {
-                if guess_word_vector[i] != answer_word_vector[i]
-                    && let Some(&x) = guess_appearance.get(&guess_word_vector[i])
-                    && x > 0
-                {
-                    s_status[i] = 'Y';
-                    *guess_appearance.entry(guess_word_vector[i]).or_insert(0) -= 1;
-                }
-
-                match s_status[i] {
-                    'G' => {
-                        green_list.push((guess_word_vector[i], i as i32));
-                        chracter_status[((guess_word_vector[i] as u8) - b'a') as usize] = 'G'
-                    }
-                    'Y' if chracter_status[((guess_word_vector[i] as u8) - b'a') as usize]
-                        != 'G' =>
-                    {
-                        yellow_list.push(guess_word_vector[i]);
-                        chracter_status[((guess_word_vector[i] as u8) - b'a') as usize] = 'Y'
-                    }
-                    'R' if (chracter_status[((guess_word_vector[i] as u8) - b'a') as usize]
-                        != 'G'
-                        && chracter_status[((guess_word_vector[i] as u8) - b'a') as usize]
-                            != 'Y') =>
-                    {
-                        chracter_status[((guess_word_vector[i] as u8) - b'a') as usize] = 'R';
-                    }
-                    _ => continue,
-                }
+        //english keeps the exact case rules of the line protocol
+        let word = match cli.lang {
+            Some(_) => cli.language.normalize(&guess),
+            None => guess.trim().to_string(),
+        };
+        let word = word.as_str();
+        let s_status = match round.submit(word, accept_list) {
+            Ok(x) => x,
+            Err(e) if jsonl => {
+                Event::invalid(word, e).emit();
+                continue;
             }
-            flag = true;
-            for iter in s_status.iter() {
-                pr(*iter);
-                if *iter != 'G' {
-                    flag = false;
-                }
+            Err(e) if cli.accessible => {
+                println!("INVALID: {}.", e.message());
+                continue;
             }
-            print!(" ");
-            for iter in chracter_status.iter() {
-                pr(*iter);
+            Err(_) => {
+                println!("INVALID");
+                continue;
             }
-            println!();
+        };
+        *guess_list.entry(word.to_uppercase()).or_insert(0) += 1;
+        game.guesses.push(word.to_uppercase());
+        game.times.push(started.elapsed().as_millis() as u64);
+
+        if jsonl {
+            Event::guess(&round).emit();
+            continue;
         }
-        if flag {
-            break;
+        if cli.accessible {
+            print_accessible(&round);
+            continue;
         }
-        if input_flag {
-            turn += 1;
+
+        for iter in s_status.iter() {
+            pr(*iter, &cli.palette);
         }
-        s_status.clear();
-        guess.clear();
-        guess_appearance.clear();
-        guess_word_vector.clear();
+        print!(" ");
+        for iter in round.chracter_status.iter() {
+            pr(*iter, &cli.palette);
+        }
+        println!();
     }
+    let seconds = game.seconds();
     json_data.games.push(game);
-    if flag {
-        println!("CORRECT {}", turn);
-        turn
+    if jsonl {
+        Event::GameEnd {
+            won: round.won(),
+            turns: round.guesses.len(),
+            answer: round.answer().to_uppercase(),
+            seconds,
+        }
+        .emit();
+        return if round.won() {
+            round.guesses.len() as i32
+        } else {
+            0
+        };
+    }
+    if round.won() {
+        println!("CORRECT {}", round.guesses.len());
+        round.guesses.len() as i32
     } else {
         println!("FAILED {}", answer_word.trim().to_uppercase());
         0
     }
 }
 
-fn load_word_list(path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    //load answer word list
-    let content = std::fs::read_to_string(path)?;
-    let words: Vec<String> = content
-        .lines()
-        .map(|line| line.trim().to_lowercase()) //lowercase
-        .filter(|word| !word.is_empty())
-        .collect();
-
-    if words.is_empty() {
-        //empty? illegal
-        return Err("Empty".into());
-    }
-    let unique_words: HashSet<&str> = words.iter().map(|x| &x[..]).collect(); //->&str easy to use is_subset
-    if unique_words.len() != words.len() {
-        return Err("repeat".into());
-    }
-    let final_set: HashSet<&str> = FINAL.iter().copied().collect();
-    if !unique_words.is_subset(&final_set) {
-        //subset of FINAL
-        return Err("not subset".into());
-    }
-    let mut sorted_words = words;
-    sorted_words.sort(); //letter order
-    Ok(sorted_words)
+fn load_lists(cli: &Cli) -> Result<(Vec<String>, WordSet), Box<dyn std::error::Error>> {
+    //answer and guess lists as given, see shuffle_answers
+    words::load_lists(
+        cli.final_repo.as_deref(),
+        cli.accept_repo.as_deref(),
+        cli.custom_lists,
+        &cli.language,
+    )
+}
+
+fn shuffle_answers(cli: &Cli, final_list: &mut [String]) {
+    //answer order of the session, -d walks it
+    if cli.seed.is_none() {
+        //not given, shown so the session can be played again
+        eprintln!(
+            "Seed: {} (-s {} replays this session)",
+            cli.session_seed, cli.session_seed
+        );
+    }
+    let mut rng = StdRng::seed_from_u64(cli.session_seed);
+    freq::shuffle(final_list, cli.frequencies.as_ref(), &mut rng);
+}
+
+fn open_state(cli: &Cli) -> Result<Option<Box<dyn StateStore>>, Box<dyn std::error::Error>> {
+    //-S for the long running commands, a missing json file is created by the first game
+    Ok(match cli.state {
+        Some(ref x) if !x.exists() && !store::is_sqlite_path(x) => {
+            Some(Box::new(store::JsonStore::create(x)?))
+        }
+        Some(ref x) => Some(store::open_store(x)?),
+        None => None,
+    })
+}
+
+fn ask_player(
+    state_store: Option<&dyn StateStore>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    //the name prompt, listing the players of the state file
+    print!("{}", "Your name: ".bold().blue());
+    if let Some(x) = state_store {
+        let players = x.players()?;
+        if !players.is_empty() {
+            print!("({}) ", players.join(", "));
+        }
+    }
+    io::stdout().flush().unwrap();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).expect("cannot read");
+    let name = line.trim();
+    Ok((!name.is_empty()).then(|| name.to_string()))
 }
 
-fn load_accept_list(path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    //load guess word list
-    let content = std::fs::read_to_string(path)?;
-    let words: Vec<String> = content
-        .lines()
-        .map(|line| line.trim().to_lowercase())
-        .filter(|word| !word.is_empty())
-        .collect();
-
-    if words.is_empty() {
-        return Err("Empty".into());
-    }
-    let unique_words: HashSet<&str> = words.iter().map(|x| &x[..]).collect();
-    if unique_words.len() != words.len() {
-        return Err("repeat".into());
-    }
-    let final_set: HashSet<&str> = ACCEPTABLE.iter().copied().collect(); // only change
-    if !unique_words.is_subset(&final_set) {
-        return Err("not subset".into());
-    }
-    let mut sorted_words = words;
-    sorted_words.sort();
-    Ok(sorted_words)
+fn with_player(
+    cli: &Cli,
+    state_store: Option<&dyn StateStore>,
+) -> Result<Cli, Box<dyn std::error::Error>> {
+    //speedrun and survival are recorded like games: -P, else asked at a tty
+    let mut cli = cli.clone();
+    if cli.player.is_none() && frontend(&cli).1 {
+        cli.player = ask_player(state_store)?;
+    }
+    Ok(cli)
 }
 
-fn load_state_json(path: &PathBuf) -> Result<JsonState, Box<dyn std::error::Error>> {
-    //load state json and return Result
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let u = serde_json::from_reader(reader)?;
-    Ok(u)
+fn frontend(cli: &Cli) -> (PlayFn, bool) {
+    //frontend of the game subcommands and whether it is a tty one
+    let is_tty = atty::is(atty::Stream::Stdout) && cli.format != events::Format::Jsonl;
+    let play: PlayFn = if cli.tui && is_tty {
+        tui::play_tui
+    } else if is_tty {
+        play_tty
+    } else {
+        play_dis_tty
+    };
+    (play, is_tty)
 }
 
-fn write_state_json(
-    //write json Result
-    path: &PathBuf,
-    json_data: &JsonState,
+fn speedrun(
+    cli: &Cli,
+    puzzles: usize,
+    final_list: &[String],
+    accept_list: &WordSet,
+    mut state_store: Option<Box<dyn StateStore>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let file = File::create(path)?;
-    serde_json::to_writer_pretty(file, &json_data)?;
+    //the seeded answers from -d on, one after the other, timed as a whole
+    let jsonl = cli.format == events::Format::Jsonl;
+    let (play, is_tty) = frontend(cli);
+    let mut json_data = JsonState::default(); //games of this run
+    let mut solved = 0;
+    let started = Instant::now();
+    for i in 0..puzzles {
+        if is_tty {
+            println!(
+                "Puzzle {}/{}, {} so far",
+                i + 1,
+                puzzles,
+                clock(started.elapsed().as_secs())
+            );
+        }
+        let mut round_cli = cli.clone();
+        round_cli.words = Some(final_list[(cli.days.max(1) - 1 + i) % final_list.len()].clone());
+        round_cli.rand_verbos = false;
+        round_cli.days = 1;
+        round_cli.seed = None;
+        round_cli.mode = Some("speedrun".to_string());
+        if play(
+            &round_cli,
+            &mut Vec::new(),
+            &mut BTreeMap::new(),
+            final_list,
+            accept_list,
+            &mut json_data,
+            0,
+        ) > 0
+        {
+            solved += 1;
+        }
+        let Some(game) = json_data.games.last() else {
+            break;
+        };
+        if let Some(ref mut x) = state_store {
+            x.record_game(game)?;
+        }
+    }
+    let seconds = started.elapsed().as_secs_f64();
+    if jsonl {
+        Event::Speedrun {
+            puzzles,
+            solved,
+            seconds,
+        }
+        .emit();
+    } else if is_tty {
+        println!("Solved {}/{} in {}", solved, puzzles, clock(seconds as u64));
+        for (i, game) in json_data.games.iter().enumerate() {
+            println!(
+                "{:>3} {} {} {}",
+                i + 1,
+                game.answer,
+                if game.won() { "solved" } else { "failed" },
+                game.seconds()
+                    .map_or("-".to_string(), |x| format!("{:.1}s", x))
+            );
+        }
+    } else {
+        println!("SPEEDRUN {} {} {:.1}", solved, puzzles, seconds);
+    }
+    Ok(())
+}
+
+fn run_command(cli: &Cli, command: &Commands) -> Result<(), Box<dyn std::error::Error>> {
+    if cli.lang.is_some()
+        && matches!(
+            command,
+            Commands::Referee { .. } | Commands::Serve { .. } | Commands::Race { .. }
+        )
+    {
+        //their protocols and clients speak a-z
+        return Err(String::from("referee, serve and race are english only, drop --lang").into());
+    }
+    let (from, to, force) = match command {
+        Commands::Referee {
+            bots,
+            games,
+            timeout,
+            format,
+        } => {
+            let (mut final_list, accept_list) = load_lists(cli)?;
+            shuffle_answers(cli, &mut final_list);
+            let board = referee::run(
+                cli,
+                bots,
+                *games,
+                std::time::Duration::from_secs(*timeout),
+                &final_list,
+                &accept_list,
+            )?;
+            match format {
+                leaderboard::OutputFormat::Table => print!("{}", referee::format_table(&board)),
+                leaderboard::OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&board)?)
+                }
+            }
+            return Ok(());
+        }
+        Commands::Serve { port, ttl } => {
+            let (final_list, accept_list) = load_lists(cli)?;
+            let state_store = open_state(cli)?;
+            let mut server = server::GameServer::new(
+                final_list,
+                accept_list,
+                state_store,
+                std::time::Duration::from_secs(*ttl),
+                cli.session_seed,
+                cli.frequencies.clone(),
+            );
+            return server.run(*port);
+        }
+        Commands::Race { port } => {
+            let (mut final_list, accept_list) = load_lists(cli)?;
+            shuffle_answers(cli, &mut final_list);
+            let state_store = open_state(cli)?;
+            return race::host(
+                *port,
+                final_list,
+                cli.days.max(1) - 1,
+                accept_list,
+                state_store,
+            );
+        }
+        Commands::Duel {
+            players,
+            rounds,
+            any_word,
+        } => {
+            if players[0] == players[1] {
+                return Err(String::from("a duel needs two different players").into());
+            }
+            if !atty::is(atty::Stream::Stdout) || cli.format == events::Format::Jsonl {
+                return Err(String::from("duel is played at a terminal").into());
+            }
+            let (final_list, accept_list) = load_lists(cli)?;
+            let state_store = open_state(cli)?;
+            return duel::run(
+                cli,
+                players,
+                *rounds,
+                *any_word,
+                &final_list,
+                &accept_list,
+                state_store,
+            );
+        }
+        Commands::Speedrun { puzzles } => {
+            let (mut final_list, accept_list) = load_lists(cli)?;
+            shuffle_answers(cli, &mut final_list);
+            let state_store = open_state(cli)?;
+            let cli = with_player(cli, state_store.as_deref())?;
+            return speedrun(&cli, *puzzles, &final_list, &accept_list, state_store);
+        }
+        Commands::Survival => {
+            let (mut final_list, accept_list) = load_lists(cli)?;
+            shuffle_answers(cli, &mut final_list);
+            let state_store = open_state(cli)?;
+            let cli = with_player(cli, state_store.as_deref())?;
+            return survival::run(&cli, &final_list, &accept_list, state_store);
+        }
+        Commands::Lists { command } => {
+            return lists::run(
+                command,
+                cli.final_repo.as_deref(),
+                cli.accept_repo.as_deref(),
+                cli.custom_lists,
+                &cli.language,
+                cli.frequencies.as_ref(),
+            );
+        }
+        Commands::Import { json, db, force } => (json, db, *force),
+        Commands::Export { db, json, force } => (db, json, *force),
+        Commands::Leaderboard {
+            metric,
+            from,
+            to,
+            mode,
+            format,
+        } => {
+            let Some(ref path) = cli.state else {
+                return Err(String::from("leaderboard needs a state file, use -S").into());
+            };
+            let filter = leaderboard::Filter {
+                from: from.as_deref().map(leaderboard::parse_date).transpose()?,
+                to: to
+                    .as_deref()
+                    .map(leaderboard::parse_date)
+                    .transpose()?
+                    .map(|x| x + 86400), //whole last day
+                mode: mode.clone(),
+            };
+            let json_data = store::open_store(path)?.load()?;
+            if mode.as_deref() == Some("survival") {
+                let entries = leaderboard::rank_runs(&json_data, &filter);
+                match format {
+                    leaderboard::OutputFormat::Table => {
+                        print!("{}", leaderboard::format_runs(&entries))
+                    }
+                    leaderboard::OutputFormat::Json => {
+                        println!("{}", serde_json::to_string_pretty(&entries)?)
+                    }
+                }
+                return Ok(());
+            }
+            let entries = leaderboard::rank(&json_data, &filter, *metric);
+            match format {
+                leaderboard::OutputFormat::Table => {
+                    print!("{}", leaderboard::format_table(&entries))
+                }
+                leaderboard::OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&entries)?)
+                }
+            }
+            return Ok(());
+        }
+    };
+    //convert between json and sqlite state files
+    if !from.exists() {
+        //opening a missing database would create an empty one
+        return Err(format!("{}: no such state file", from.display()).into());
+    }
+    if to.exists() && !force {
+        return Err(format!(
+            "{} already exists, --force to add the games missing from it",
+            to.display()
+        )
+        .into());
+    }
+    let source = store::open_store(from)?;
+    let mut target: Box<dyn StateStore> = if to.exists() || store::is_sqlite_path(to) {
+        store::open_store(to)?
+    } else {
+        Box::new(store::JsonStore::create(to)?)
+    };
+    let (games, runs) = store::copy_state(source.as_ref(), target.as_mut())?;
+    if runs > 0 {
+        println!("{} games and {} survival runs copied", games, runs);
+    } else {
+        println!("{} games copied", games);
+    }
     Ok(())
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let is_tty = atty::is(atty::Stream::Stdout);
     let cli = Cli::parse();
-    let merged_cli = merge_config(&cli)?;
-
-    let mut json_data: JsonState = JsonState {
-        total_rounds: 0,
-        games: Vec::new(),
-    };
+    let mut merged_cli = merge_config(&cli)?;
+    //jsonl is for programs, so never the interactive frontends
+    let jsonl = merged_cli.format == events::Format::Jsonl;
+    if jsonl && merged_cli.tui {
+        return Err(String::from("--format jsonl can't be used with --tui").into());
+    }
+    let is_tty = atty::is(atty::Stream::Stdout) && !jsonl;
+    if theme::no_color() {
+        colored::control::set_override(false);
+    }
+    if let Some(ref command) = cli.command {
+        return run_command(&merged_cli, command);
+    }
 
-    if let Some(ref __) = merged_cli.state {
+    let mut state_store: Option<Box<dyn StateStore>> = None;
+    if let Some(ref x) = merged_cli.state {
         // state data
-        match load_state_json(&merged_cli.state.clone().unwrap()) {
-            Result::Ok(x) => json_data = x,
-            Err(st) => {
-                json_data.total_rounds = 0; //json data wrong
-                return Err(st);
-            }
+        state_store = Some(store::open_store(x)?);
+    }
+    if merged_cli.new_cycle && state_store.is_none() {
+        return Err(
+            String::from("--new-cycle needs the state file (-S) that keeps the cycle").into(),
+        );
+    }
+    if is_tty {
+        println!(
+            "I am in a tty. Please print {}!",
+            "colorful characters".bold().red()
+        );
+        if merged_cli.player.is_none() {
+            merged_cli.player = ask_player(state_store.as_deref())?; //games are attributed to this profile
         }
+        println!(
+            "Welcome to wordle, {}!",
+            merged_cli.player.as_deref().unwrap_or("")
+        );
+        io::stdout().flush().unwrap();
     }
+
+    let mut json_data = JsonState::default(); //games of this session
+
     let mut answer_list: Vec<String> = Vec::new();
     let mut guess_list: BTreeMap<String, i32> = BTreeMap::new(); //ordered by appearence frequency
 
-    let mut final_list: Vec<String>;
-    if let Some(ref x) = merged_cli.final_repo {
-        // given answer repo
-        match load_word_list(x) {
-            std::result::Result::Ok(x) => final_list = x,
-            std::result::Result::Err(_x) => return Err(String::from("load error").into()),
-        } // or use .unwrap() directly
-    } else {
-        final_list = FINAL.iter().map(|&s| s.to_string()).collect();
+    if merged_cli.join.is_some() && merged_cli.lang.is_some() {
+        return Err(String::from("races are english only, drop --lang").into());
+    }
+    if let Some(ref url) = merged_cli.join {
+        return tui::play_race(&merged_cli, url);
     }
-    let mut rng = if let Some(seed) = merged_cli.seed {
-        StdRng::seed_from_u64(seed)
-    } else {
-        StdRng::seed_from_u64(42) //default
-    };
-    final_list.shuffle(&mut rng);
 
-    let accept_list: Vec<String>;
-    if let Some(ref x) = merged_cli.accept_repo {
-        //given guess repo
-        accept_list = load_accept_list(x).unwrap();
-    } else {
-        accept_list = ACCEPTABLE.iter().map(|&s| s.to_string()).collect();
+    let (mut final_list, accept_list) = load_lists(&merged_cli)?;
+    if merged_cli.rand_verbos {
+        shuffle_answers(&merged_cli, &mut final_list);
     }
 
-    if is_tty {
-        match merged_cli.words {
-            Some(ref _x) => {
-                //given word 1round
-                let success_flag = play_tty(
-                    &merged_cli,
-                    &mut answer_list,
-                    &mut guess_list,
-                    &final_list,
-                    &accept_list,
-                    &mut json_data,
-                    merged_cli.days - 1,
-                );
-                if success_flag == 10000 {
-                    return Err(String::from("mood mix!").into()); //mood mix
-                }
-                Ok(())
-            }
+    //frontend for one round, they share the same contract
+    let (play, _) = frontend(&merged_cli);
 
-            None => {
-                let mut turns_record: i32 = 0;
-                let mut success_record: i32 = 0;
-                let mut try_record: i32 = 0;
-                if let Some(__) = &merged_cli.state {
-                    //organize historic state infomation
-                    for iter in json_data.games.iter() {
-                        if iter.guesses[iter.guesses.len() - 1] == iter.answer {
-                            //guess success
-                            success_record += 1;
-                            try_record += iter.guesses.len() as i32;
-                        }
-                        for words_iter in 0..iter.guesses.len() {
-                            //update guess frequency
-                            *guess_list
-                                .entry(iter.guesses[words_iter].trim().to_lowercase().to_string())
-                                .or_insert(0) += 1;
-                        }
-                    }
-                }
-                loop {
-                    let success_flag = play_tty(
-                        &merged_cli,
-                        &mut answer_list,
-                        &mut guess_list,
-                        &final_list,
-                        &accept_list,
-                        &mut json_data,
-                        merged_cli.days - 1 + turns_record as usize,
-                    );
-                    turns_record += 1;
-                    json_data.total_rounds += 1;
-                    if success_flag == 10000 {
-                        return Err(String::from("mood mix!").into());
-                    }
-                    if success_flag > 0 {
-                        //success
-                        success_record += 1;
-                        try_record += success_flag;
-                    }
-                    if let Some(x) = &merged_cli.state {
-                        //write json data
-                        write_state_json(x, &json_data)?;
-                    }
-                    if merged_cli.status_verbos {
-                        //calculate ratio
-                        //io::stdout().flush().unwrap();
-                        if success_record > 0 {
-                            //avoid NaN
-                            println!(
-                                "{} {} {:.2}",
-                                success_record,
-                                json_data.total_rounds - success_record,
-                                try_record as f32 / success_record as f32
-                            );
-                        } else {
-                            println!("0 {} 0.00", json_data.total_rounds - success_record);
-                        }
-                        //io::stdout().flush().unwrap();
-                        let mut entries: Vec<(&String, &i32)> = guess_list.iter().collect();
-                        entries.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
-                        //the 5 most guesses words
-                        for iter in entries.iter().take(5) {
-                            io::stdout().flush().unwrap();
-                            print!("{} {} ", iter.0.to_uppercase(), iter.1);
-                        }
-                        println!(" ");
-                    }
+    if merged_cli.words.is_some() {
+        //given word 1round
+        let success_flag = play(
+            &merged_cli,
+            &mut answer_list,
+            &mut guess_list,
+            &final_list,
+            &accept_list,
+            &mut json_data,
+            merged_cli.days.max(1) - 1,
+        );
+        if success_flag == 10000 {
+            return Err(String::from("mood mix!").into()); //mood mix
+        }
+        return Ok(());
+    }
 
-                    //io::stdout().flush().unwrap();
-                    println!("Do you want a new try? [Y/n]");
-                    let mut cont = String::new();
-                    std::io::stdin().read_line(&mut cont).expect("cannot read");
-                    if cont.trim() != "Y" {
-                        break Ok(());
-                    }
-                }
-            }
+    let mut turns_record: i32 = 0;
+    let mut success_record: i32 = 0;
+    let mut try_record: i32 = 0;
+    let mut streak = store::Stats::default(); //only the streak and time fields are used
+    if let Some(ref mut x) = state_store {
+        //organize historic state infomation of this player
+        let stats = x.stats(merged_cli.player.as_deref())?;
+        json_data.total_rounds = stats.total_rounds;
+        success_record = stats.success;
+        try_record = stats.success_tries;
+        streak.current_streak = stats.current_streak;
+        streak.max_streak = stats.max_streak;
+        streak.fastest_solve = stats.fastest_solve;
+        streak.average_time = stats.average_time;
+        streak.timed_solves = stats.timed_solves;
+        guess_list = stats.guess_count;
+        if merged_cli.new_cycle {
+            x.reset_cycle(merged_cli.player.as_deref(), store::now())?;
         }
-    } else {
-        match merged_cli.words {
-            Some(ref _x) => {
-                let success_flag = play_dis_tty(
-                    &merged_cli,
-                    &mut answer_list,
-                    &mut guess_list,
-                    &final_list,
-                    &accept_list,
-                    &mut json_data,
-                    merged_cli.days - 1,
+        if merged_cli.rand_verbos {
+            //skip the answers this player had in earlier sessions
+            answer_list =
+                store::cycle_answers(&x.past_answers(merged_cli.player.as_deref())?, &final_list);
+        }
+    }
+    loop {
+        let success_flag = play(
+            &merged_cli,
+            &mut answer_list,
+            &mut guess_list,
+            &final_list,
+            &accept_list,
+            &mut json_data,
+            merged_cli.days.max(1) - 1 + turns_record as usize,
+        );
+        turns_record += 1;
+        json_data.total_rounds += 1;
+        if success_flag == 10000 {
+            return Err(String::from("mood mix!").into());
+        }
+        if success_flag > 0 {
+            //success
+            success_record += 1;
+            try_record += success_flag;
+        }
+        streak.push_result(success_flag > 0);
+        if success_flag > 0
+            && let Some(x) = json_data.games.last().and_then(|x| x.seconds())
+        {
+            streak.push_time(x);
+        }
+        if let Some(ref mut x) = state_store
+            && let Some(game) = json_data.games.last()
+        {
+            //write the finished game
+            x.record_game(game)?;
+        }
+        if merged_cli.status_verbos && jsonl {
+            let mut entries: Vec<(&String, &i32)> = guess_list.iter().collect();
+            entries.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            Event::Stats {
+                wins: success_record,
+                losses: json_data.total_rounds - success_record,
+                avg_guesses: if success_record > 0 {
+                    try_record as f64 / success_record as f64
+                } else {
+                    0.0
+                },
+                top_guesses: entries
+                    .iter()
+                    .take(5)
+                    .map(|x| events::WordCount {
+                        word: x.0.to_uppercase(),
+                        count: *x.1,
+                    })
+                    .collect(),
+                fastest_solve: streak.fastest_solve,
+                average_time: streak.average_time,
+            }
+            .emit();
+        } else if merged_cli.status_verbos {
+            //calculate ratio
+            if success_record > 0 {
+                //avoid NaN
+                println!(
+                    "{} {} {:.2}",
+                    success_record,
+                    json_data.total_rounds - success_record,
+                    try_record as f32 / success_record as f32
                 );
-                if success_flag == 10000 {
-                    return Err(String::from("mood mix!").into());
-                }
-                Ok(())
+            } else {
+                println!("0 {} 0.00", json_data.total_rounds - success_record);
             }
-            None => {
-                let mut turns_record: i32 = 0;
-                let mut success_record: i32 = 0;
-                let mut try_record: i32 = 0;
-                if let Some(__) = &merged_cli.state {
-                    for iter in json_data.games.iter() {
-                        if iter.guesses[iter.guesses.len() - 1] == iter.answer {
-                            success_record += 1;
-                            try_record += iter.guesses.len() as i32;
-                        }
-                        for words_iter in 0..iter.guesses.len() {
-                            let key = iter.guesses[words_iter].trim().to_string();
-                            *guess_list.entry(key).or_insert(0) += 1;
-                        }
-                    }
-                }
-                loop {
-                    let success_flag = play_dis_tty(
-                        &merged_cli,
-                        &mut answer_list,
-                        &mut guess_list,
-                        &final_list,
-                        &accept_list,
-                        &mut json_data,
-                        (merged_cli.days - 1) + turns_record as usize,
+            io::stdout().flush().unwrap();
+            let mut entries: Vec<(&String, &i32)> = guess_list.iter().collect();
+            entries.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            //the 5 most guesses words
+            let output: String = entries
+                .iter()
+                .take(5)
+                .map(|iter| format!("{} {}", iter.0.to_uppercase(), iter.1))
+                .collect::<Vec<String>>()
+                .join(" ");
+            println!("{}", output);
+            if is_tty {
+                println!(
+                    "{} streak: {} (best {})",
+                    merged_cli.player.as_deref().unwrap_or("Current"),
+                    streak.current_streak,
+                    streak.max_streak
+                );
+                if let (Some(fastest), Some(average)) = (streak.fastest_solve, streak.average_time)
+                {
+                    println!(
+                        "Fastest solve: {} Average time: {}",
+                        clock(fastest as u64),
+                        clock(average as u64)
                     );
-                    turns_record += 1;
-                    json_data.total_rounds += 1;
-                    if success_flag == 10000 {
-                        return Err(String::from("mood mix!").into());
-                    }
-                    if success_flag > 0 {
-                        success_record += 1;
-                        try_record += success_flag;
-                    }
-
-                    if merged_cli.status_verbos {
-                        //io::stdout().flush().unwrap();
-                        if success_record > 0 {
-                            println!(
-                                "{} {} {:.2}",
-                                success_record,
-                                json_data.total_rounds - success_record,
-                                try_record as f32 / success_record as f32
-                            );
-                        } else {
-                            println!("0 {} 0.00", json_data.total_rounds - success_record);
-                        }
-                        io::stdout().flush().unwrap();
-                        let mut entries: Vec<(&String, &i32)> = guess_list.iter().collect();
-                        entries.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
-                        let output: String = entries
-                            .iter()
-                            .take(5)
-                            .map(|iter| format!("{} {}", iter.0.to_uppercase(), iter.1))
-                            .collect::<Vec<String>>()
-                            .join(" ");
-
-                        println!("{}", output);
-                    }
-
-                    if let Some(x) = &merged_cli.state {
-                        write_state_json(x, &json_data)?;
-                    }
-
-                    io::stdout().flush().unwrap();
-                    let mut cont = String::new();
-                    std::io::stdin().read_line(&mut cont).expect("cannot read");
-                    if cont.trim() != "Y" {
-                        break;
-                    }
-                }
-                match &merged_cli.state {
-                    Some(_x) => Ok(()),
-                    _ => Ok(()),
                 }
             }
         }
+
+        if is_tty {
+            println!("Do you want a new try? [Y/n]");
+        }
+        io::stdout().flush().unwrap();
+        let mut cont = String::new();
+        std::io::stdin().read_line(&mut cont).expect("cannot read");
+        if cont.trim() != "Y" {
+            break Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        let mut full = vec!["wordle"];
+        full.extend_from_slice(args);
+        merge_config(&Cli::parse_from(full)).unwrap()
+    }
+
+    fn answers(cli: &Cli, rounds: usize) -> Vec<String> {
+        //the answers of a -r session from -d on
+        let (mut final_list, _) = load_lists(cli).unwrap();
+        shuffle_answers(cli, &mut final_list);
+        let mut answer_list = Vec::new();
+        (0..rounds)
+            .map(|i| choose_answer(cli, &mut answer_list, &final_list, cli.days - 1 + i, false))
+            .collect()
+    }
+
+    #[test]
+    fn session_seed_comes_from_s_or_d() {
+        assert_eq!(cli(&["-r", "-s", "5"]).session_seed, 5);
+        assert_eq!(cli(&["-r", "-d", "3"]).session_seed, DEFAULT_SEED);
+        assert_eq!(cli(&["-r", "-d", "3", "-s", "9"]).session_seed, 9);
+    }
+
+    #[test]
+    fn a_seed_replays_the_session() {
+        let first = answers(&cli(&["-r", "-s", "123"]), 10);
+        assert_eq!(first, answers(&cli(&["-r", "-s", "123"]), 10));
+        assert_ne!(first, answers(&cli(&["-r", "-s", "124"]), 10));
+        //a drawn seed replays like a given one
+        let drawn = cli(&["-r"]);
+        let seed = drawn.session_seed.to_string();
+        assert_eq!(answers(&drawn, 10), answers(&cli(&["-r", "-s", &seed]), 10));
+    }
+
+    #[test]
+    fn days_walk_the_same_order() {
+        let all = answers(&cli(&["-r", "-s", "7"]), 6);
+        assert_eq!(answers(&cli(&["-r", "-s", "7", "-d", "4"]), 3), all[3..]);
+        //everyone gets the same word on a day
+        assert_eq!(
+            answers(&cli(&["-r", "-d", "2"]), 1),
+            answers(&cli(&["-r", "-d", "2", "-s", &DEFAULT_SEED.to_string()]), 1)
+        );
+    }
+
+    #[test]
+    fn freq_weighted_sessions_replay_too() {
+        let path = crate::testutil::temp_file("freq.txt", "crane 1000\nslate 500\n");
+        let path = path.to_str().unwrap();
+        let weighted = answers(&cli(&["-r", "-s", "3", "--freq", path]), 5);
+        assert_eq!(
+            weighted,
+            answers(&cli(&["-r", "-s", "3", "--freq", path]), 5)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn answers_skip_the_cycle_and_wrap() {
+        let cli = cli(&["-r", "-s", "1"]);
+        let final_list: Vec<String> = ["crane", "slate", "abbey"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        //slate came up in an earlier session
+        let mut answer_list = vec!["slate".to_string()];
+        let mut choose = |id| choose_answer(&cli, &mut answer_list, &final_list, id, false);
+        assert_eq!(choose(1), "abbey");
+        assert_eq!(choose(2), "crane");
+        //every word came up, a new cycle starts where the walk is
+        assert_eq!(choose(3), "crane");
+        assert_eq!(choose(4), "slate");
+        assert_eq!(choose(5), "abbey");
+        assert_eq!(choose(6), "crane");
     }
 }
@@ -0,0 +1,685 @@
+use crate::engine::{MAX_TURNS, Round, WORD_LEN};
+use crate::store::{self, Game, StateStore};
+use crate::words::WordSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+//clients connect to ws://host:port/<room> and send json messages tagged by "type"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Join { player: String },
+    Start, //anyone in the lobby can start the next race
+    Guess { word: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Standing {
+    pub player: String,
+    pub won: bool,
+    pub turns: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Lobby {
+        room: String,
+        players: Vec<String>,
+        racing: bool,
+    },
+    Start {
+        race: usize, //races played in this room, 1 for the first
+        word_length: usize,
+        max_turns: usize,
+        players: Vec<String>,
+    },
+    Feedback {
+        word: String,
+        feedback: String, //GYR..., for your own guess
+    },
+    Invalid {
+        word: String,
+        reason: String,
+        message: String,
+    },
+    Progress {
+        player: String,
+        feedback: String, //an opponent's colors, never their letters
+    },
+    Finished {
+        player: String,
+        won: bool,
+        turns: usize,
+    },
+    Result {
+        winner: Option<String>, //first to solve
+        answer: String,
+        standings: Vec<Standing>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+fn feedback_string(feedback: &[char]) -> String {
+    feedback.iter().collect()
+}
+
+//----- host -----
+
+enum HubEvent {
+    Connect {
+        id: u64,
+        room: String,
+        sender: mpsc::Sender<ServerMessage>,
+    },
+    Message {
+        id: u64,
+        message: ClientMessage,
+    },
+    Disconnect {
+        id: u64,
+    },
+}
+
+struct Racer {
+    name: String,
+    round: Round,
+    game: Game,
+    racing: bool, //joined before the current race started
+}
+
+#[derive(Default)]
+struct Room {
+    racers: BTreeMap<u64, Racer>,
+    racing: bool,
+    races: usize,
+    winner: Option<String>,
+    answer: String,
+    started: Option<Instant>, //of the current race, for guess times
+}
+
+struct Hub {
+    rooms: HashMap<String, Room>,
+    clients: HashMap<u64, (String, mpsc::Sender<ServerMessage>)>, //room and outbox
+    final_list: Vec<String>,                                      //already shuffled by -s
+    first: usize,                                                 //-d, first answer index
+    accept_list: WordSet,
+    state_store: Option<Box<dyn StateStore>>,
+}
+
+impl Hub {
+    fn send(&self, id: u64, message: ServerMessage) {
+        if let Some((_, sender)) = self.clients.get(&id) {
+            let _ = sender.send(message);
+        }
+    }
+
+    fn broadcast(&self, room: &str, message: ServerMessage, except: Option<u64>) {
+        for (id, (x, sender)) in self.clients.iter() {
+            if x == room && Some(*id) != except {
+                let _ = sender.send(message.clone());
+            }
+        }
+    }
+
+    fn lobby(&self, room: &str) {
+        let Some(x) = self.rooms.get(room) else {
+            return;
+        };
+        self.broadcast(
+            room,
+            ServerMessage::Lobby {
+                room: room.to_string(),
+                players: x.racers.values().map(|r| r.name.clone()).collect(),
+                racing: x.racing,
+            },
+            None,
+        );
+    }
+
+    fn handle(&mut self, event: HubEvent) {
+        match event {
+            HubEvent::Connect { id, room, sender } => {
+                self.clients.insert(id, (room.clone(), sender));
+                self.rooms.entry(room.clone()).or_default();
+                self.lobby(&room);
+            }
+            HubEvent::Message { id, message } => {
+                let Some((room, _)) = self.clients.get(&id).cloned() else {
+                    return;
+                };
+                if let Err(e) = self.message(id, &room, message) {
+                    self.send(id, ServerMessage::Error { message: e });
+                }
+            }
+            HubEvent::Disconnect { id } => {
+                let Some((room, _)) = self.clients.remove(&id) else {
+                    return;
+                };
+                if let Some(x) = self.rooms.get_mut(&room)
+                    && let Some(racer) = x.racers.remove(&id)
+                    && x.racing
+                    && racer.racing
+                {
+                    //leaving mid race counts as a loss
+                    if let Some(ref mut store) = self.state_store {
+                        let _ = store.record_game(&racer.game);
+                    }
+                    self.finish_if_done(&room);
+                }
+                if self.clients.values().any(|(x, _)| *x == room) {
+                    self.lobby(&room);
+                } else {
+                    //the last one out closes the room
+                    self.rooms.remove(&room);
+                }
+            }
+        }
+    }
+
+    fn message(&mut self, id: u64, room: &str, message: ClientMessage) -> Result<(), String> {
+        let x = self.rooms.get_mut(room).unwrap();
+        match message {
+            ClientMessage::Join { player } => {
+                let player = player.trim().to_string();
+                if player.is_empty() {
+                    return Err("empty player name".to_string());
+                }
+                if x.racers.iter().any(|(i, r)| r.name == player && *i != id) {
+                    return Err(format!("{} is already in this room", player));
+                }
+                if x.racing && x.racers.contains_key(&id) {
+                    //would drop the racer's round, like Start
+                    return Err("a race is running".to_string());
+                }
+                x.racers.insert(
+                    id,
+                    Racer {
+                        name: player,
+                        round: Round::default(),
+                        game: Game::default(),
+                        racing: false,
+                    },
+                );
+                self.lobby(room);
+            }
+            ClientMessage::Start => {
+                if !x.racers.contains_key(&id) {
+                    return Err("join the room first".to_string());
+                }
+                if x.racing {
+                    return Err("a race is running".to_string());
+                }
+                //every room walks the seeded list from -d on
+                let answer =
+                    self.final_list[(self.first + x.races) % self.final_list.len()].clone();
+                x.races += 1;
+                x.racing = true;
+                x.winner = None;
+                x.answer = answer.clone();
+                x.started = Some(Instant::now());
+                for racer in x.racers.values_mut() {
+                    racer.round = Round::new(&answer, false);
+                    racer.game = Game {
+                        answer: answer.to_uppercase(),
+                        guesses: Vec::new(),
+                        player: Some(racer.name.clone()),
+                        played_at: Some(store::now()),
+                        mode: Some("race".to_string()),
+                        opponent: None,
+                        times: Vec::new(),
+                    };
+                    racer.racing = true;
+                }
+                let start = ServerMessage::Start {
+                    race: x.races,
+                    word_length: WORD_LEN,
+                    max_turns: MAX_TURNS,
+                    players: x.racers.values().map(|r| r.name.clone()).collect(),
+                };
+                self.broadcast(room, start, None);
+            }
+            ClientMessage::Guess { word } => {
+                let racer = match x.racers.get_mut(&id) {
+                    Some(r) if x.racing && r.racing && !r.round.finished() => r,
+                    _ => return Err("not in a running race".to_string()),
+                };
+                let word = word.trim().to_lowercase();
+                let feedback = match racer.round.submit(&word, &self.accept_list) {
+                    Ok(x) => feedback_string(&x),
+                    Err(e) => {
+                        self.send(
+                            id,
+                            ServerMessage::Invalid {
+                                word,
+                                reason: e.code().to_string(),
+                                message: e.message().to_string(),
+                            },
+                        );
+                        return Ok(());
+                    }
+                };
+                racer.game.guesses.push(word.to_uppercase());
+                if let Some(started) = x.started {
+                    racer.game.times.push(started.elapsed().as_millis() as u64);
+                }
+                let name = racer.name.clone();
+                let (finished, won, turns) = (
+                    racer.round.finished(),
+                    racer.round.won(),
+                    racer.round.guesses.len(),
+                );
+                if won && x.winner.is_none() {
+                    x.winner = Some(name.clone());
+                }
+                self.send(
+                    id,
+                    ServerMessage::Feedback {
+                        word,
+                        feedback: feedback.clone(),
+                    },
+                );
+                self.broadcast(
+                    room,
+                    ServerMessage::Progress {
+                        player: name.clone(),
+                        feedback,
+                    },
+                    Some(id),
+                );
+                if finished {
+                    self.broadcast(
+                        room,
+                        ServerMessage::Finished {
+                            player: name,
+                            won,
+                            turns,
+                        },
+                        None,
+                    );
+                    self.finish_if_done(room);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish_if_done(&mut self, room: &str) {
+        //the first solve ends the race, otherwise everyone runs out of turns
+        let x = self.rooms.get_mut(room).unwrap();
+        let racers: Vec<&mut Racer> = x.racers.values_mut().filter(|r| r.racing).collect();
+        if x.winner.is_none() && racers.iter().any(|r| !r.round.finished()) {
+            return;
+        }
+        let mut standings = Vec::new();
+        for racer in racers {
+            racer.racing = false;
+            if let Some(ref mut store) = self.state_store
+                && let Err(e) = store.record_game(&racer.game)
+            {
+                eprintln!("cannot record game: {}", e);
+            }
+            standings.push(Standing {
+                player: racer.name.clone(),
+                won: racer.round.won(),
+                turns: racer.round.guesses.len(),
+            });
+        }
+        //solvers first, by turns
+        standings.sort_by_key(|s| (!s.won, s.turns));
+        x.racing = false;
+        let result = ServerMessage::Result {
+            winner: x.winner.clone(),
+            answer: x.answer.to_uppercase(),
+            standings,
+        };
+        self.broadcast(room, result, None);
+        self.lobby(room);
+    }
+}
+
+#[allow(clippy::result_large_err)] //the handshake callback type is tungstenite's
+fn connection(stream: TcpStream, id: u64, hub: mpsc::Sender<HubEvent>) {
+    //socket <-> hub, polls both sides with a short read timeout
+    let mut room = String::new();
+    let callback = |request: &Request, response: Response| {
+        room = request.uri().path().trim_matches('/').to_string();
+        Ok(response)
+    };
+    let Ok(mut socket) = tungstenite::accept_hdr(stream, callback) else {
+        return;
+    };
+    if room.is_empty() {
+        room = "lobby".to_string();
+    }
+    let _ = socket
+        .get_mut()
+        .set_read_timeout(Some(Duration::from_millis(20)));
+    let (sender, outbox) = mpsc::channel();
+    if hub.send(HubEvent::Connect { id, room, sender }).is_err() {
+        return;
+    }
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(message) => {
+                    let _ = hub.send(HubEvent::Message { id, message });
+                }
+                Err(e) => {
+                    let error = ServerMessage::Error {
+                        message: format!("bad message: {}", e),
+                    };
+                    let _ = socket.send(Message::Text(serde_json::to_string(&error).unwrap()));
+                }
+            },
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(ref e))
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+        let mut closed = false;
+        while let Ok(message) = outbox.try_recv() {
+            let text = serde_json::to_string(&message).unwrap();
+            if socket.send(Message::Text(text)).is_err() {
+                closed = true;
+                break;
+            }
+        }
+        if closed {
+            break;
+        }
+    }
+    let _ = hub.send(HubEvent::Disconnect { id });
+}
+
+pub fn host(
+    port: u16,
+    final_list: Vec<String>,
+    first: usize,
+    accept_list: WordSet,
+    state_store: Option<Box<dyn StateStore>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    //localhost only, rooms are created by joining them
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("race rooms on ws://127.0.0.1:{}/<room>", port);
+    let (sender, events) = mpsc::channel();
+    std::thread::spawn(move || {
+        for (id, stream) in listener.incoming().enumerate() {
+            let Ok(stream) = stream else { continue };
+            let hub = sender.clone();
+            std::thread::spawn(move || connection(stream, id as u64, hub));
+        }
+    });
+    //one thread owns the rooms and the state store
+    let mut hub = Hub {
+        rooms: HashMap::new(),
+        clients: HashMap::new(),
+        final_list,
+        first,
+        accept_list,
+        state_store,
+    };
+    for event in events {
+        hub.handle(event);
+    }
+    Ok(())
+}
+
+//----- client -----
+
+pub struct RaceClient {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl RaceClient {
+    pub fn connect(url: &str, player: &str) -> Result<RaceClient, Box<dyn std::error::Error>> {
+        let (mut socket, _) = tungstenite::connect(url)?;
+        if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+            stream.set_read_timeout(Some(Duration::from_millis(5)))?;
+        }
+        let mut client = RaceClient { socket };
+        client.send(&ClientMessage::Join {
+            player: player.to_string(),
+        })?;
+        Ok(client)
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> Result<(), String> {
+        let text = serde_json::to_string(message).unwrap();
+        self.socket
+            .send(Message::Text(text))
+            .map_err(|e| format!("connection lost: {}", e))
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<ServerMessage>, String> {
+        //whatever arrived, without blocking
+        let mut messages = Vec::new();
+        loop {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => {
+                    if let Ok(x) = serde_json::from_str(&text) {
+                        messages.push(x);
+                    }
+                }
+                Ok(Message::Close(_)) => return Err("server closed the room".to_string()),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(ref e))
+                    if e.kind() == std::io::ErrorKind::WouldBlock
+                        || e.kind() == std::io::ErrorKind::TimedOut =>
+                {
+                    return Ok(messages);
+                }
+                Err(e) => return Err(format!("connection lost: {}", e)),
+            }
+        }
+    }
+}
+
+//what a client shows, fed by server messages
+#[derive(Default)]
+pub struct RaceView {
+    pub room: String,
+    pub players: Vec<String>,
+    pub racing: bool,
+    pub round: Round, //own guesses, scored by the server
+    pub opponents: BTreeMap<String, Vec<Vec<char>>>, //feedback rows only
+    pub result: Option<(Option<String>, String)>, //winner, answer
+    pub standings: Vec<Standing>,
+    pub message: String,
+}
+
+impl RaceView {
+    pub fn handle(&mut self, message: ServerMessage, me: &str) {
+        match message {
+            ServerMessage::Lobby {
+                room,
+                players,
+                racing,
+            } => {
+                self.room = room;
+                self.players = players;
+                self.racing = racing;
+            }
+            ServerMessage::Start { race, players, .. } => {
+                self.racing = true;
+                self.round = Round::new("", false);
+                self.opponents = players
+                    .into_iter()
+                    .filter(|x| x != me)
+                    .map(|x| (x, Vec::new()))
+                    .collect();
+                self.result = None;
+                self.standings.clear();
+                self.message = format!("Race {} started!", race);
+            }
+            ServerMessage::Feedback { word, feedback } => {
+                let feedback: Vec<char> = feedback.chars().collect();
+                self.round.apply(&word, &feedback);
+                self.message.clear();
+            }
+            ServerMessage::Invalid { message, .. } => self.message = message,
+            ServerMessage::Progress { player, feedback } => {
+                self.opponents
+                    .entry(player)
+                    .or_default()
+                    .push(feedback.chars().collect());
+            }
+            ServerMessage::Finished { player, won, turns } => {
+                if player != me {
+                    self.message = if won {
+                        format!("{} solved it in {}", player, turns)
+                    } else {
+                        format!("{} is out of turns", player)
+                    };
+                }
+            }
+            ServerMessage::Result {
+                winner,
+                answer,
+                standings,
+            } => {
+                self.racing = false;
+                self.message = match winner {
+                    Some(ref x) if x == me => format!("You won! The word was {}", answer),
+                    Some(ref x) => format!("{} won. The word was {}", x, answer),
+                    None => format!("Nobody solved it. The word was {}", answer),
+                };
+                self.result = Some((winner, answer));
+                self.standings = standings;
+            }
+            ServerMessage::Error { message } => self.message = message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::SqliteStore;
+    use std::path::Path;
+
+    fn hub() -> Hub {
+        let words = ["crane", "slate", "react", "abbey"];
+        Hub {
+            rooms: HashMap::new(),
+            clients: HashMap::new(),
+            final_list: vec!["crane".to_string()],
+            first: 0,
+            accept_list: WordSet::new(words, &('a'..='z').collect::<Vec<char>>()),
+            state_store: Some(Box::new(SqliteStore::open(Path::new(":memory:")).unwrap())),
+        }
+    }
+
+    fn join(hub: &mut Hub, id: u64, player: &str) -> mpsc::Receiver<ServerMessage> {
+        let (sender, outbox) = mpsc::channel();
+        let room = "friday".to_string();
+        hub.handle(HubEvent::Connect { id, room, sender });
+        let player = player.to_string();
+        send(hub, id, ClientMessage::Join { player });
+        outbox
+    }
+
+    fn send(hub: &mut Hub, id: u64, message: ClientMessage) {
+        hub.handle(HubEvent::Message { id, message });
+    }
+
+    fn guess(hub: &mut Hub, id: u64, word: &str) {
+        let word = word.to_string();
+        send(hub, id, ClientMessage::Guess { word });
+    }
+
+    fn received(outbox: &mpsc::Receiver<ServerMessage>) -> Vec<serde_json::Value> {
+        outbox.try_iter().map(|x| serde_json::json!(x)).collect()
+    }
+
+    #[test]
+    fn first_solve_wins_and_everyone_is_recorded() {
+        let mut hub = hub();
+        let ann = join(&mut hub, 1, "ann");
+        let _bob = join(&mut hub, 2, "bob");
+        send(&mut hub, 2, ClientMessage::Start);
+        guess(&mut hub, 2, "slate");
+        guess(&mut hub, 1, "crane");
+        let result = received(&ann)
+            .into_iter()
+            .find(|x| x["type"] == "result")
+            .unwrap();
+        assert_eq!(result["winner"], "ann");
+        assert_eq!(result["answer"], "CRANE");
+        assert_eq!(result["standings"][0]["player"], "ann");
+        assert_eq!(result["standings"][1]["won"], false);
+        //bob's unfinished game is a loss in the state file
+        let state = hub.state_store.as_ref().unwrap().load().unwrap();
+        let games: Vec<(Option<&str>, &[String])> = state
+            .games
+            .iter()
+            .map(|x| (x.player.as_deref(), x.guesses.as_slice()))
+            .collect();
+        assert_eq!(
+            games,
+            [
+                (Some("ann"), &["CRANE".to_string()][..]),
+                (Some("bob"), &["SLATE".to_string()][..])
+            ]
+        );
+        assert!(
+            state
+                .games
+                .iter()
+                .all(|x| x.mode.as_deref() == Some("race"))
+        );
+        assert!(!hub.rooms["friday"].racing);
+    }
+
+    #[test]
+    fn opponents_see_colors_only() {
+        let mut hub = hub();
+        let ann = join(&mut hub, 1, "ann");
+        let bob = join(&mut hub, 2, "bob");
+        send(&mut hub, 1, ClientMessage::Start);
+        received(&ann);
+        received(&bob);
+        guess(&mut hub, 1, "react");
+        let own = received(&ann);
+        assert_eq!(own[0]["type"], "feedback");
+        assert_eq!(own[0]["word"], "react");
+        let seen = received(&bob);
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0]["type"], "progress");
+        assert_eq!(seen[0]["player"], "ann");
+        assert_eq!(seen[0]["feedback"], "YYGYR");
+        assert!(!seen[0].to_string().to_lowercase().contains("react"));
+    }
+
+    #[test]
+    fn racers_keep_their_round_and_empty_rooms_close() {
+        let mut hub = hub();
+        let ann = join(&mut hub, 1, "ann");
+        send(&mut hub, 1, ClientMessage::Start);
+        guess(&mut hub, 1, "slate");
+        received(&ann);
+        //joining again mid race would start ann over
+        send(
+            &mut hub,
+            1,
+            ClientMessage::Join {
+                player: "ann".to_string(),
+            },
+        );
+        let error = received(&ann);
+        assert_eq!(error[0]["message"], "a race is running");
+        assert_eq!(hub.rooms["friday"].racers[&1].round.guesses.len(), 1);
+        hub.handle(HubEvent::Disconnect { id: 1 });
+        assert!(hub.rooms.is_empty());
+    }
+}
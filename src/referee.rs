@@ -0,0 +1,281 @@
+use crate::Cli;
+use crate::engine::{MAX_TURNS, Round, WORD_LEN};
+use crate::words::WordSet;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+//referee -> bot, one message per line:
+//  wordle <version> <word length> <max turns> <final count> <accept count> <games> <difficult 0|1>
+//  game <n>                     answer picked, send the first guess
+//  feedback <GYR...>            hint for the last guess, send the next one
+//  invalid <reason>             guess rejected and the turn lost, send another
+//  result win <turns> <ANSWER>  or  result lose <ANSWER>
+//  end
+//bot -> referee: one guess per line after game, feedback and invalid
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Score {
+    pub rank: usize,
+    pub bot: String,
+    pub games: usize,
+    pub wins: usize,
+    pub avg_guesses: f64,      //over won games, 0 when none
+    pub invalid: usize,        //rejected guesses
+    pub seconds: f64,          //total time the bot took to answer
+    pub error: Option<String>, //why the bot stopped early
+}
+
+struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    lines: mpsc::Receiver<String>, //stdout, read on a thread so a stuck bot can time out
+    timeout: Duration,
+    thinking: Duration,
+}
+
+impl Bot {
+    fn spawn(command: &str, timeout: Duration) -> Result<Bot, Box<dyn std::error::Error>> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or("empty bot command")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot start bot {}: {}", command, e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Bot {
+            child,
+            stdin,
+            lines,
+            timeout,
+            thinking: Duration::ZERO,
+        })
+    }
+
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", message)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| "bot closed its input".to_string())
+    }
+
+    fn ask(&mut self, message: &str) -> Result<String, String> {
+        //send a message and wait for the guess
+        self.send(message)?;
+        let start = Instant::now();
+        let reply = self.lines.recv_timeout(self.timeout);
+        self.thinking += start.elapsed();
+        match reply {
+            Ok(line) => Ok(line.trim().to_lowercase()),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                Err(format!("no guess within {}s", self.timeout.as_secs_f64()))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err("bot exited".to_string()),
+        }
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn play(
+    bot: &mut Bot,
+    n: usize,
+    answer: &str,
+    difficult: bool,
+    accept_list: &WordSet,
+    score: &mut Score,
+) -> Result<(), String> {
+    //one game, invalid guesses use up a turn so a bot can't stall
+    let mut round = Round::new(answer, difficult);
+    let mut turns = 0;
+    let mut message = format!("game {}", n);
+    while turns < MAX_TURNS && !round.won() {
+        let guess = bot.ask(&message)?;
+        turns += 1;
+        match round.submit(&guess, accept_list) {
+            Ok(feedback) => message = format!("feedback {}", feedback.iter().collect::<String>()),
+            Err(e) => {
+                score.invalid += 1;
+                message = format!("invalid {}", e.code());
+            }
+        }
+    }
+    score.games += 1;
+    if round.won() {
+        score.wins += 1;
+        score.avg_guesses += turns as f64; //summed here, divided at the end
+        bot.send(&format!("result win {} {}", turns, answer.to_uppercase()))
+    } else {
+        bot.send(&format!("result lose {}", answer.to_uppercase()))
+    }
+}
+
+pub fn run(
+    cli: &Cli,
+    bots: &[String],
+    games: usize,
+    timeout: Duration,
+    final_list: &[String],
+    accept_list: &WordSet,
+) -> Result<Vec<Score>, Box<dyn std::error::Error>> {
+    //every bot gets the same answers: the shuffled final set from -d on
+    let answers: Vec<&String> = (0..games)
+        .map(|i| &final_list[(cli.days.max(1) - 1 + i) % final_list.len()])
+        .collect();
+    let mut board = Vec::new();
+    for command in bots.iter() {
+        let mut bot = Bot::spawn(command, timeout)?;
+        let mut score = Score {
+            rank: 0,
+            bot: command.clone(),
+            games: 0,
+            wins: 0,
+            avg_guesses: 0.0,
+            invalid: 0,
+            seconds: 0.0,
+            error: None,
+        };
+        let header = format!(
+            "wordle {} {} {} {} {} {} {}",
+            PROTOCOL_VERSION,
+            WORD_LEN,
+            MAX_TURNS,
+            final_list.len(),
+            accept_list.len(),
+            games,
+            cli.diff_verbos as u8
+        );
+        let mut result = bot.send(&header);
+        for (i, answer) in answers.iter().enumerate() {
+            if result.is_err() {
+                break;
+            }
+            result = play(
+                &mut bot,
+                i + 1,
+                answer,
+                cli.diff_verbos,
+                accept_list,
+                &mut score,
+            );
+        }
+        if let Err(e) = result {
+            //the games it didn't finish count as lost
+            score.error = Some(e);
+            score.games = games;
+        } else {
+            let _ = bot.send("end");
+        }
+        if score.wins > 0 {
+            score.avg_guesses /= score.wins as f64;
+        }
+        score.seconds = bot.thinking.as_secs_f64();
+        eprintln!("{}: {}/{} solved", command, score.wins, games);
+        board.push(score);
+    }
+    board.sort_by(|a, b| {
+        b.wins
+            .cmp(&a.wins)
+            .then(a.avg_guesses.total_cmp(&b.avg_guesses))
+            .then(a.seconds.total_cmp(&b.seconds))
+    });
+    for (i, score) in board.iter_mut().enumerate() {
+        score.rank = i + 1;
+    }
+    Ok(board)
+}
+
+pub fn format_table(board: &[Score]) -> String {
+    let mut out = format!(
+        "{:<5}{:<24}{:>6}{:>6}{:>8}{:>8}{:>9}{:>9}\n",
+        "RANK", "BOT", "GAMES", "WINS", "WIN%", "AVG", "INVALID", "SECONDS"
+    );
+    for x in board.iter() {
+        let win_rate = if x.games > 0 {
+            x.wins as f64 * 100.0 / x.games as f64
+        } else {
+            0.0
+        };
+        out += &format!(
+            "{:<5}{:<24}{:>6}{:>6}{:>7.1}%{:>8.2}{:>9}{:>9.2}\n",
+            x.rank, x.bot, x.games, x.wins, win_rate, x.avg_guesses, x.invalid, x.seconds
+        );
+        if let Some(ref e) = x.error {
+            out += &format!("     stopped: {}\n", e);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn bot(name: &str, script: &str) -> String {
+        let path = crate::testutil::temp_file(name, script);
+        format!("sh {}", path.display())
+    }
+
+    #[test]
+    fn bad_and_silent_bots_lose() {
+        let cli = crate::Cli::parse_from(["wordle"]);
+        let final_list = ["crane".to_string(), "slate".to_string()];
+        let accept_list = WordSet::new(["crane", "slate"], &('a'..='z').collect::<Vec<char>>());
+        let guesses = |word: &str| {
+            format!(
+                "while read line; do case $line in end) exit;; result*) ;; *) echo {};; esac; done\n",
+                word
+            )
+        };
+        let bots = [
+            bot("crane.sh", &guesses("crane")),
+            bot("invalid.sh", &guesses("zzzzz")),
+            bot("silent.sh", "read header\nexec sleep 5\n"),
+        ];
+        let board = run(
+            &cli,
+            &bots,
+            2,
+            Duration::from_millis(200),
+            &final_list,
+            &accept_list,
+        )
+        .unwrap();
+        let score = |i: usize| board.iter().find(|x| x.bot == bots[i]).unwrap();
+        assert_eq!(board[0].bot, bots[0]);
+        assert_eq!((score(0).wins, score(0).avg_guesses), (1, 1.0));
+        //every rejected guess costs a turn
+        assert_eq!(
+            (score(1).games, score(1).wins, score(1).invalid),
+            (2, 0, 12)
+        );
+        assert_eq!(score(1).error, None);
+        //the games it never answered are lost
+        assert_eq!((score(2).games, score(2).wins), (2, 0));
+        assert_eq!(score(2).error.as_deref(), Some("no guess within 0.2s"));
+        for command in bots {
+            std::fs::remove_file(command.trim_start_matches("sh ")).unwrap();
+        }
+    }
+}
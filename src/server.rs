@@ -0,0 +1,444 @@
+use crate::engine::{MAX_TURNS, Round, WORD_LEN, status_word, upper};
+use crate::events::Letter;
+use crate::freq::{self, Frequencies};
+use crate::store::{self, Game, StateStore};
+use crate::words::WordSet;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Response, Server};
+
+//POST /games {"mode","seed","day","length","player"}   new game
+//GET  /games/<id>                                       state, the answer once finished
+//POST /games/<id>/guesses {"word"}                      play a guess
+//GET  /stats?player=<name>                              stats of the state file
+
+const MAX_BODY: u64 = 64 * 1024; //larger bodies get 413
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct NewGame {
+    mode: Option<String>, //normal (default) or difficult
+    seed: Option<u64>,    //same answer order as -s
+    day: Option<usize>,   //like -d, with seed
+    length: Option<usize>,
+    player: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NewGuess {
+    word: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    word: String,
+    feedback: Vec<Letter>,
+}
+
+#[derive(Debug, Serialize)]
+struct GameState {
+    id: String,
+    mode: String,
+    player: Option<String>,
+    word_length: usize,
+    max_turns: usize,
+    guesses: Vec<Row>,
+    alphabet: BTreeMap<char, &'static str>, //every tried letter
+    finished: bool,
+    won: bool,
+    answer: Option<String>, //hidden until the game is over
+}
+
+struct Session {
+    round: Round,
+    game: Game,
+    used: Instant,    //last request, for expiry
+    started: Instant, //for guess times
+}
+
+impl Session {
+    fn state(&self, id: &str) -> GameState {
+        let round = &self.round;
+        let guesses = round
+            .guesses
+            .iter()
+            .zip(round.feedback.iter())
+            .map(|(word, feedback)| Row {
+                word: word.to_uppercase(),
+                feedback: word
+                    .chars()
+                    .zip(feedback.iter())
+                    .map(|(c, &status)| Letter {
+                        letter: upper(c),
+                        status: status_word(status),
+                    })
+                    .collect(),
+            })
+            .collect();
+        GameState {
+            id: id.to_string(),
+            mode: self
+                .game
+                .mode
+                .clone()
+                .unwrap_or_else(|| "normal".to_string()),
+            player: self.game.player.clone(),
+            word_length: WORD_LEN,
+            max_turns: MAX_TURNS,
+            guesses,
+            alphabet: round
+                .alphabet
+                .iter()
+                .filter(|&&c| round.letter_status(c) != 'X')
+                .map(|&c| (upper(c), status_word(round.letter_status(c))))
+                .collect(),
+            finished: round.finished(),
+            won: round.won(),
+            answer: round.finished().then(|| round.answer().to_uppercase()),
+        }
+    }
+}
+
+pub struct GameServer {
+    sessions: HashMap<String, Session>,
+    ttl: Duration,
+    final_list: Vec<String>, //builtin order, shuffled per request seed
+    accept_list: WordSet,
+    state_store: Option<Box<dyn StateStore>>,
+    seed: u64,
+    rng: rand::rngs::StdRng, //answers of games without a seed, reproducible from seed
+    frequencies: Option<Frequencies>,
+}
+
+type Reply = (u16, serde_json::Value);
+
+fn error(code: u16, message: &str) -> Reply {
+    (code, serde_json::json!({ "error": message }))
+}
+
+fn body<T: for<'a> Deserialize<'a>>(text: &str) -> Result<T, Reply> {
+    let text = if text.trim().is_empty() { "{}" } else { text };
+    serde_json::from_str(text).map_err(|e| error(400, &format!("bad json: {}", e)))
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    //query values: + is a space, %XX a byte of utf-8, None when malformed
+    let mut bytes = Vec::new();
+    let mut rest = text.bytes();
+    while let Some(c) = rest.next() {
+        match c {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [rest.next()?, rest.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            _ => bytes.push(c),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+impl GameServer {
+    pub fn new(
+        final_list: Vec<String>,
+        accept_list: WordSet,
+        state_store: Option<Box<dyn StateStore>>,
+        ttl: Duration,
+        seed: u64,                        //-s or drawn at start
+        frequencies: Option<Frequencies>, //--freq, weights the answers like the command line
+    ) -> GameServer {
+        GameServer {
+            sessions: HashMap::new(),
+            ttl,
+            final_list,
+            accept_list,
+            state_store,
+            seed,
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+            frequencies,
+        }
+    }
+
+    pub fn run(&mut self, port: u16) -> Result<(), Box<dyn std::error::Error>> {
+        //localhost only, one request at a time
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| format!("cannot listen on port {}: {}", port, e))?;
+        eprintln!(
+            "listening on http://127.0.0.1:{} (seed {})",
+            port, self.seed
+        );
+        for mut request in server.incoming_requests() {
+            self.expire();
+            let mut text = String::new();
+            //one byte past the limit tells a large body from one at the limit
+            let read = request
+                .as_reader()
+                .take(MAX_BODY + 1)
+                .read_to_string(&mut text);
+            let (code, value) = match read {
+                Ok(n) if n as u64 > MAX_BODY => error(413, "body larger than 64 KiB"),
+                Ok(_) => self.handle(request.method(), request.url(), &text),
+                Err(_) => error(400, "cannot read body"),
+            };
+            let response = Response::from_string(value.to_string())
+                .with_status_code(code)
+                .with_header("Content-Type: application/json".parse::<Header>().unwrap());
+            let _ = request.respond(response);
+        }
+        Ok(())
+    }
+
+    fn expire(&mut self) {
+        let ttl = self.ttl;
+        self.sessions.retain(|_, x| x.used.elapsed() < ttl);
+    }
+
+    fn handle(&mut self, method: &Method, url: &str, text: &str) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, parts.as_slice()) {
+            (Method::Post, ["games"]) => match body(text) {
+                Ok(x) => self.create(x),
+                Err(e) => e,
+            },
+            (Method::Get, ["games", id]) => match self.sessions.get_mut(*id) {
+                Some(x) => {
+                    x.used = Instant::now();
+                    (200, serde_json::json!(x.state(id)))
+                }
+                None => error(404, "no such game, it may have expired"),
+            },
+            (Method::Post, ["games", id, "guesses"]) => match body(text) {
+                Ok(x) => self.guess(id, x),
+                Err(e) => e,
+            },
+            (Method::Get, ["stats"]) => {
+                let player = match query.split('&').find_map(|x| x.strip_prefix("player=")) {
+                    Some(x) => match percent_decode(x) {
+                        Some(x) => Some(x).filter(|x| !x.is_empty()),
+                        None => return error(400, "bad player in the query"),
+                    },
+                    None => None,
+                };
+                match self.state_store {
+                    Some(ref x) => match x.stats(player.as_deref()) {
+                        Ok(stats) => (200, serde_json::json!(stats)),
+                        Err(e) => error(500, &e.to_string()),
+                    },
+                    None => error(404, "no state file, start the server with -S"),
+                }
+            }
+            _ => error(404, "unknown endpoint"),
+        }
+    }
+
+    fn create(&mut self, new: NewGame) -> Reply {
+        if new.length.is_some_and(|x| x != WORD_LEN) {
+            return error(
+                400,
+                &format!("only {} letter words are supported", WORD_LEN),
+            );
+        }
+        let mode = new.mode.unwrap_or_else(|| "normal".to_string());
+        if mode != "normal" && mode != "difficult" {
+            return error(400, "mode must be normal or difficult");
+        }
+        //same order as the command line with -s and -d, weighted by --freq alike
+        let mut list = self.final_list.clone();
+        let answer = match new.seed {
+            Some(seed) => {
+                let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+                freq::shuffle(&mut list, self.frequencies.as_ref(), &mut rng);
+                list[(new.day.unwrap_or(1).max(1) - 1) % list.len()].clone()
+            }
+            None => {
+                freq::shuffle(&mut list, self.frequencies.as_ref(), &mut self.rng);
+                list[0].clone()
+            }
+        };
+        //not from the seed, ids mustn't be guessable
+        let id = format!("{:016x}", rand::thread_rng().r#gen::<u64>());
+        let session = Session {
+            round: Round::new(&answer, mode == "difficult"),
+            game: Game {
+                answer: answer.to_uppercase(),
+                guesses: Vec::new(),
+                player: new.player,
+                played_at: Some(store::now()),
+                mode: Some(mode),
+                opponent: None,
+                times: Vec::new(),
+            },
+            used: Instant::now(),
+            started: Instant::now(),
+        };
+        let state = session.state(&id);
+        self.sessions.insert(id, session);
+        (201, serde_json::json!(state))
+    }
+
+    fn guess(&mut self, id: &str, new: NewGuess) -> Reply {
+        let Some(session) = self.sessions.get_mut(id) else {
+            return error(404, "no such game, it may have expired");
+        };
+        session.used = Instant::now();
+        if session.round.finished() {
+            return error(409, "game is over");
+        }
+        let word = new.word.trim().to_lowercase();
+        if let Err(e) = session.round.submit(&word, &self.accept_list) {
+            return (
+                422,
+                serde_json::json!({ "error": e.message(), "reason": e.code() }),
+            );
+        }
+        session.game.guesses.push(word.to_uppercase());
+        session
+            .game
+            .times
+            .push(session.started.elapsed().as_millis() as u64);
+        if session.round.finished()
+            && let Some(ref mut x) = self.state_store
+            && let Err(e) = x.record_game(&session.game)
+        {
+            eprintln!("cannot record game: {}", e);
+        }
+        (200, serde_json::json!(session.state(id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::SqliteStore;
+    use clap::Parser;
+    use serde_json::{Value, json};
+    use std::path::Path;
+
+    fn server(final_list: &[&str]) -> GameServer {
+        let words = ["crane", "slate", "react", "abbey"];
+        GameServer::new(
+            final_list.iter().map(|x| x.to_string()).collect(),
+            WordSet::new(words, &('a'..='z').collect::<Vec<char>>()),
+            Some(Box::new(SqliteStore::open(Path::new(":memory:")).unwrap())),
+            Duration::from_secs(60),
+            7,
+            None,
+        )
+    }
+
+    fn create(server: &mut GameServer, body: &str) -> (String, Value) {
+        let (code, value) = server.handle(&Method::Post, "/games", body);
+        assert_eq!(code, 201, "{}", value);
+        (value["id"].as_str().unwrap().to_string(), value)
+    }
+
+    fn guess(server: &mut GameServer, id: &str, word: &str) -> Reply {
+        server.handle(
+            &Method::Post,
+            &format!("/games/{}/guesses", id),
+            &json!({ "word": word }).to_string(),
+        )
+    }
+
+    #[test]
+    fn plays_a_game_and_records_it() {
+        let mut server = server(&["crane"]);
+        let (id, state) = create(&mut server, r#"{"player": "José Q"}"#);
+        assert_eq!(state["mode"], "normal");
+        assert_eq!(state["answer"], Value::Null);
+
+        let (code, state) = guess(&mut server, &id, "REACT");
+        assert_eq!(code, 200);
+        assert_eq!(state["guesses"][0]["word"], "REACT");
+        assert_eq!(state["guesses"][0]["feedback"][2]["status"], "correct");
+        assert_eq!(state["finished"], false);
+        let (code, state) = guess(&mut server, &id, "zzzzz");
+        assert_eq!(code, 422);
+        assert_eq!(state["reason"], "not_in_list");
+
+        let (code, state) = guess(&mut server, &id, "crane");
+        assert_eq!(code, 200);
+        assert_eq!(
+            (&state["won"], &state["answer"]),
+            (&json!(true), &json!("CRANE"))
+        );
+        assert_eq!(guess(&mut server, &id, "crane").0, 409);
+        let (code, state) = server.handle(&Method::Get, &format!("/games/{}", id), "");
+        assert_eq!(
+            (code, &state["guesses"].as_array().unwrap().len()),
+            (200, &2)
+        );
+
+        //the finished game is in the state file, names are percent-decoded
+        let (code, stats) = server.handle(&Method::Get, "/stats?player=Jos%C3%A9+Q", "");
+        assert_eq!(code, 200);
+        assert_eq!(
+            (&stats["total_rounds"], &stats["success"]),
+            (&json!(1), &json!(1))
+        );
+        let (_, stats) = server.handle(&Method::Get, "/stats?player=nobody", "");
+        assert_eq!(stats["total_rounds"], 0);
+        assert_eq!(server.handle(&Method::Get, "/stats?player=%E", "").0, 400);
+    }
+
+    #[test]
+    fn seeded_games_follow_the_command_line_order() {
+        let finals = crate::testutil::temp_file("final.txt", "crane\nslate\nreact\nabbey\n");
+        let table = crate::testutil::temp_file("freq.txt", "crane 1000\nslate 500\n");
+        for freq in [None, Some(table.to_str().unwrap())] {
+            //the answers wordle -r -s 3 -d <day> would give
+            let mut args = vec!["wordle", "-r", "-s", "3", "-f", finals.to_str().unwrap()];
+            args.extend(freq.iter().flat_map(|x| ["--freq", x]));
+            let cli = crate::merge_config(&crate::Cli::parse_from(args)).unwrap();
+            let (mut list, accept_list) = crate::load_lists(&cli).unwrap();
+            let mut server = GameServer::new(
+                list.clone(),
+                accept_list,
+                None,
+                Duration::from_secs(60),
+                7,
+                cli.frequencies.clone(),
+            );
+            crate::shuffle_answers(&cli, &mut list);
+            for day in 1..=5 {
+                let (id, _) = create(&mut server, &json!({"seed": 3, "day": day}).to_string());
+                let answer = server.sessions[&id].round.answer();
+                assert_eq!(answer, list[(day - 1) % list.len()], "freq {:?}", freq);
+            }
+        }
+        std::fs::remove_file(finals).unwrap();
+        std::fs::remove_file(table).unwrap();
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let mut server = server(&["crane"]);
+        let (code, value) = server.handle(&Method::Post, "/games", "{not json");
+        assert_eq!(code, 400);
+        assert!(value["error"].as_str().unwrap().starts_with("bad json"));
+        assert_eq!(
+            server.handle(&Method::Post, "/games", r#"{"length": 6}"#).0,
+            400
+        );
+        assert_eq!(
+            server
+                .handle(&Method::Post, "/games", r#"{"mode": "hard"}"#)
+                .0,
+            400
+        );
+        //an empty body is a default game
+        let (id, _) = create(&mut server, "");
+        assert_eq!(
+            server
+                .handle(&Method::Post, &format!("/games/{}/guesses", id), "[]")
+                .0,
+            400
+        );
+        assert_eq!(guess(&mut server, "nope", "crane").0, 404);
+        assert_eq!(server.handle(&Method::Get, "/nothing", "").0, 404);
+    }
+}
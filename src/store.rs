@@ -0,0 +1,800 @@
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct JsonState {
+    #[serde(default)] //default: allow {} empty json file
+    pub total_rounds: i32,
+    #[serde(default)]
+    pub games: Vec<Game>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runs: Vec<Run>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    //player ("" without a name) -> unix seconds their answer cycle was restarted
+    pub cycles: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Game {
+    pub answer: String,
+    pub guesses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] //old files have no player
+    pub player: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] //unix seconds
+    pub played_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    //normal, difficult, daily, race or duel
+    pub mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] //duel: who set the word
+    pub opponent: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")] //ms from the start to each guess
+    pub times: Vec<u64>,
+}
+
+//a survival run, kept whole so its games stay out of the per-game stats
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Run {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] //unix seconds
+    pub played_at: Option<u64>,
+    pub length: usize, //words solved
+    pub score: usize,
+    pub games: Vec<Game>, //every word of the run, the last one unsolved
+}
+
+impl Game {
+    pub fn won(&self) -> bool {
+        //the last guess hits the answer
+        self.guesses.last() == Some(&self.answer)
+    }
+
+    pub fn seconds(&self) -> Option<f64> {
+        //time to the last guess, None for untimed games
+        self.times.last().map(|&x| x as f64 / 1000.0)
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+//summary of historic games, used by -t
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    pub total_rounds: i32,
+    pub success: i32,
+    pub success_tries: i32,                 //guesses used by successful games
+    pub guess_count: BTreeMap<String, i32>, //uppercase word -> times guessed
+    pub current_streak: i32,
+    pub max_streak: i32,
+    pub fastest_solve: Option<f64>, //seconds, over timed wins
+    pub average_time: Option<f64>,  //seconds, over timed wins
+    pub timed_solves: i32,
+}
+
+impl Stats {
+    fn from_state(json_data: &JsonState, player: Option<&str>) -> Stats {
+        //player None: everyone, total_rounds then follows the file
+        let mut stats = Stats::default();
+        for game in json_data.games.iter() {
+            if player.is_some() && game.player.as_deref() != player {
+                continue;
+            }
+            stats.total_rounds += 1;
+            stats.push_result(game.won());
+            if game.won() {
+                stats.success += 1;
+                stats.success_tries += game.guesses.len() as i32;
+                if let Some(x) = game.seconds() {
+                    stats.push_time(x);
+                }
+            }
+            for word in game.guesses.iter() {
+                *stats
+                    .guess_count
+                    .entry(word.trim().to_uppercase())
+                    .or_insert(0) += 1;
+            }
+        }
+        if player.is_none() {
+            stats.total_rounds = json_data.total_rounds;
+        }
+        stats
+    }
+
+    pub fn push_result(&mut self, won: bool) {
+        //update streaks with one more game in time order
+        if won {
+            self.current_streak += 1;
+            self.max_streak = self.max_streak.max(self.current_streak);
+        } else {
+            self.current_streak = 0;
+        }
+    }
+
+    pub fn push_time(&mut self, seconds: f64) {
+        //one more timed win
+        let total = self.average_time.unwrap_or(0.0) * self.timed_solves as f64 + seconds;
+        self.timed_solves += 1;
+        self.average_time = Some(total / self.timed_solves as f64);
+        self.fastest_solve = Some(self.fastest_solve.map_or(seconds, |x| x.min(seconds)));
+    }
+}
+
+//where the game history lives: a json file or a sqlite database
+pub trait StateStore {
+    fn load(&self) -> Result<JsonState, Box<dyn std::error::Error>>; //whole history
+    fn record_game(&mut self, game: &Game) -> Result<(), Box<dyn std::error::Error>>; //append a finished game
+    fn record_run(&mut self, run: &Run) -> Result<(), Box<dyn std::error::Error>>; //append a finished survival run
+    fn stats(&self, player: Option<&str>) -> Result<Stats, Box<dyn std::error::Error>>; //None: all players
+    fn players(&self) -> Result<Vec<String>, Box<dyn std::error::Error>>;
+    fn reset_cycle(
+        &mut self,
+        player: Option<&str>,
+        at: u64,
+    ) -> Result<(), Box<dyn std::error::Error>>; //answers played before at may come again
+    fn past_answers(&self, player: Option<&str>)
+    -> Result<Vec<String>, Box<dyn std::error::Error>>; //since the cycle restart, oldest first
+}
+
+pub fn cycle_answers(past_answers: &[String], final_list: &[String]) -> Vec<String> {
+    //answers of the final list had since every word last came up, oldest first
+    let finals: HashSet<&str> = final_list.iter().map(|x| x.as_str()).collect();
+    let mut played: Vec<String> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    for answer in past_answers.iter() {
+        let answer = answer.to_lowercase();
+        if !finals.contains(answer.as_str()) || !seen.insert(answer.clone()) {
+            continue;
+        }
+        played.push(answer);
+        if seen.len() == finals.len() {
+            //list exhausted, the next cycle starts empty
+            played.clear();
+            seen.clear();
+        }
+    }
+    played
+}
+
+pub fn is_sqlite_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|x| x.to_str()),
+        Some("db") | Some("sqlite") | Some("sqlite3")
+    )
+}
+
+pub fn open_store(path: &Path) -> Result<Box<dyn StateStore>, Box<dyn std::error::Error>> {
+    //choose backend by file extension
+    if is_sqlite_path(path) {
+        Ok(Box::new(SqliteStore::open(path)?))
+    } else {
+        Ok(Box::new(JsonStore::open(path)?))
+    }
+}
+
+//a game or run is the same one when every recorded field is
+fn counts<T: Serialize>(items: &[T]) -> Result<HashMap<String, usize>, serde_json::Error> {
+    let mut counts = HashMap::new();
+    for x in items.iter() {
+        *counts.entry(serde_json::to_string(x)?).or_insert(0) += 1;
+    }
+    Ok(counts)
+}
+
+fn is_new<T: Serialize>(
+    counts: &mut HashMap<String, usize>,
+    x: &T,
+) -> Result<bool, serde_json::Error> {
+    //not matched by one left in counts, which is used up
+    Ok(match counts.get_mut(&serde_json::to_string(x)?) {
+        Some(n) if *n > 0 => {
+            *n -= 1;
+            false
+        }
+        _ => true,
+    })
+}
+
+pub fn copy_state(
+    //import/export between two stores, games already in the target are skipped
+    from: &dyn StateStore,
+    to: &mut dyn StateStore,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let json_data = from.load()?;
+    let present = to.load()?;
+    //each game or run of the target matches one of the source, so identical games played twice stay two
+    let mut games = counts(&present.games)?;
+    let mut runs = counts(&present.runs)?;
+    let (mut copied_games, mut copied_runs) = (0, 0);
+    for game in json_data.games.iter() {
+        if is_new(&mut games, game)? {
+            to.record_game(game)?;
+            copied_games += 1;
+        }
+    }
+    for run in json_data.runs.iter() {
+        if is_new(&mut runs, run)? {
+            to.record_run(run)?;
+            copied_runs += 1;
+        }
+    }
+    for (player, at) in json_data.cycles.iter() {
+        if present.cycles.get(player).is_none_or(|x| x < at) {
+            to.reset_cycle((!player.is_empty()).then_some(player.as_str()), *at)?;
+        }
+    }
+    Ok((copied_games, copied_runs))
+}
+
+pub fn load_state_json(path: &Path) -> Result<JsonState, Box<dyn std::error::Error>> {
+    //load state json and return Result
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let u = serde_json::from_reader(reader)?;
+    Ok(u)
+}
+
+pub fn write_state_json(
+    //write json Result
+    path: &Path,
+    json_data: &JsonState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, &json_data)?;
+    Ok(())
+}
+
+pub struct JsonStore {
+    path: PathBuf,
+    json_data: JsonState,
+}
+
+impl JsonStore {
+    pub fn open(path: &Path) -> Result<JsonStore, Box<dyn std::error::Error>> {
+        Ok(JsonStore {
+            path: path.to_path_buf(),
+            json_data: load_state_json(path)?,
+        })
+    }
+
+    pub fn create(path: &Path) -> Result<JsonStore, Box<dyn std::error::Error>> {
+        //start an empty file, used as export target
+        let json_data = JsonState::default();
+        write_state_json(path, &json_data)?;
+        Ok(JsonStore {
+            path: path.to_path_buf(),
+            json_data,
+        })
+    }
+}
+
+impl StateStore for JsonStore {
+    fn load(&self) -> Result<JsonState, Box<dyn std::error::Error>> {
+        Ok(self.json_data.clone())
+    }
+
+    fn record_game(&mut self, game: &Game) -> Result<(), Box<dyn std::error::Error>> {
+        self.json_data.games.push(game.clone());
+        self.json_data.total_rounds += 1;
+        write_state_json(&self.path, &self.json_data)
+    }
+
+    fn record_run(&mut self, run: &Run) -> Result<(), Box<dyn std::error::Error>> {
+        self.json_data.runs.push(run.clone());
+        write_state_json(&self.path, &self.json_data)
+    }
+
+    fn stats(&self, player: Option<&str>) -> Result<Stats, Box<dyn std::error::Error>> {
+        Ok(Stats::from_state(&self.json_data, player))
+    }
+
+    fn players(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut names: Vec<String> = self
+            .json_data
+            .games
+            .iter()
+            .filter_map(|game| game.player.clone())
+            .chain(
+                self.json_data
+                    .runs
+                    .iter()
+                    .filter_map(|run| run.player.clone()),
+            )
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    fn reset_cycle(
+        &mut self,
+        player: Option<&str>,
+        at: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.json_data
+            .cycles
+            .insert(player.unwrap_or("").to_string(), at);
+        write_state_json(&self.path, &self.json_data)
+    }
+
+    fn past_answers(
+        &self,
+        player: Option<&str>,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let started = self
+            .json_data
+            .cycles
+            .get(player.unwrap_or(""))
+            .copied()
+            .unwrap_or(0);
+        Ok(self
+            .json_data
+            .games
+            .iter()
+            .filter(|x| x.player.as_deref() == player && x.played_at.unwrap_or(0) >= started)
+            .map(|x| x.answer.clone())
+            .collect())
+    }
+}
+
+fn player_id(
+    tx: &rusqlite::Transaction,
+    name: Option<&str>,
+) -> Result<Option<i64>, rusqlite::Error> {
+    //create the profile row on first use
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    tx.execute(
+        "INSERT OR IGNORE INTO players (name) VALUES (?1)",
+        params![name],
+    )?;
+    tx.query_row(
+        "SELECT id FROM players WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )
+    .map(Some)
+}
+
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<SqliteStore, Box<dyn std::error::Error>> {
+        //create the tables on first use
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS players (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE
+            );
+            CREATE TABLE IF NOT EXISTS games (
+                id INTEGER PRIMARY KEY,
+                player_id INTEGER REFERENCES players(id),
+                answer TEXT NOT NULL,
+                won INTEGER NOT NULL,
+                turns INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS guesses (
+                game_id INTEGER NOT NULL REFERENCES games(id),
+                turn INTEGER NOT NULL,
+                word TEXT NOT NULL,
+                ms INTEGER,
+                PRIMARY KEY (game_id, turn)
+            );
+            CREATE INDEX IF NOT EXISTS guesses_word ON guesses(word);
+            CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY,
+                player_id INTEGER REFERENCES players(id),
+                played_at INTEGER,
+                length INTEGER NOT NULL,
+                score INTEGER NOT NULL,
+                games TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS cycles (
+                player TEXT PRIMARY KEY,
+                started_at INTEGER NOT NULL
+            );",
+        )?;
+        //columns added after the first release of the schema
+        let mut columns: Vec<String> = Vec::new();
+        {
+            let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('games')")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            for row in rows {
+                columns.push(row?);
+            }
+        }
+        for (column, ty) in [
+            ("played_at", "INTEGER"),
+            ("mode", "TEXT"),
+            ("opponent", "TEXT"),
+        ] {
+            if !columns.iter().any(|x| x == column) {
+                conn.execute_batch(&format!("ALTER TABLE games ADD COLUMN {column} {ty}"))?;
+            }
+        }
+        let timed: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('guesses') WHERE name = 'ms'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !timed {
+            conn.execute_batch("ALTER TABLE guesses ADD COLUMN ms INTEGER")?;
+        }
+        Ok(SqliteStore { conn })
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn load(&self) -> Result<JsonState, Box<dyn std::error::Error>> {
+        let mut games: Vec<Game> = Vec::new();
+        let mut ids: Vec<i64> = Vec::new();
+        let mut game_stmt = self.conn.prepare(
+            "SELECT games.id, games.answer, players.name, games.played_at, games.mode, games.opponent
+             FROM games LEFT JOIN players ON players.id = games.player_id
+             ORDER BY games.id",
+        )?;
+        let rows = game_stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            let played_at: Option<i64> = row.get(3)?;
+            Ok((
+                id,
+                Game {
+                    answer: row.get(1)?,
+                    guesses: Vec::new(),
+                    player: row.get(2)?,
+                    played_at: played_at.map(|x| x as u64),
+                    mode: row.get(4)?,
+                    opponent: row.get(5)?,
+                    times: Vec::new(),
+                },
+            ))
+        })?;
+        for row in rows {
+            let (id, game) = row?;
+            ids.push(id);
+            games.push(game);
+        }
+        let mut guess_stmt = self
+            .conn
+            .prepare("SELECT word, ms FROM guesses WHERE game_id = ?1 ORDER BY turn")?;
+        for (id, game) in ids.iter().zip(games.iter_mut()) {
+            let rows = guess_stmt.query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            for row in rows {
+                let (word, ms): (String, Option<i64>) = row?;
+                game.guesses.push(word);
+                if let Some(x) = ms {
+                    game.times.push(x as u64);
+                }
+            }
+        }
+        //the games of a run are kept as json, a run is only read whole
+        let mut run_stmt = self.conn.prepare(
+            "SELECT players.name, runs.played_at, runs.length, runs.score, runs.games
+             FROM runs LEFT JOIN players ON players.id = runs.player_id
+             ORDER BY runs.id",
+        )?;
+        let rows = run_stmt.query_map([], |row| {
+            let played_at: Option<i64> = row.get(1)?;
+            let length: i64 = row.get(2)?;
+            let score: i64 = row.get(3)?;
+            let games: String = row.get(4)?;
+            Ok((row.get(0)?, played_at, length, score, games))
+        })?;
+        let mut runs = Vec::new();
+        for row in rows {
+            let (player, played_at, length, score, run_games) = row?;
+            runs.push(Run {
+                player,
+                played_at: played_at.map(|x| x as u64),
+                length: length as usize,
+                score: score as usize,
+                games: serde_json::from_str(&run_games)?,
+            });
+        }
+        let mut cycles = BTreeMap::new();
+        let mut cycle_stmt = self.conn.prepare("SELECT player, started_at FROM cycles")?;
+        let rows = cycle_stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (player, at): (String, i64) = row?;
+            cycles.insert(player, at as u64);
+        }
+        Ok(JsonState {
+            total_rounds: games.len() as i32,
+            games,
+            runs,
+            cycles,
+        })
+    }
+
+    fn record_game(&mut self, game: &Game) -> Result<(), Box<dyn std::error::Error>> {
+        let tx = self.conn.transaction()?;
+        let player_id = player_id(&tx, game.player.as_deref())?;
+        tx.execute(
+            "INSERT INTO games (player_id, answer, won, turns, played_at, mode, opponent)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                player_id,
+                game.answer,
+                game.won(),
+                game.guesses.len() as i64,
+                game.played_at.map(|x| x as i64),
+                game.mode,
+                game.opponent
+            ],
+        )?;
+        let game_id = tx.last_insert_rowid();
+        for (turn, word) in game.guesses.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO guesses (game_id, turn, word, ms) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    game_id,
+                    turn as i64 + 1,
+                    word,
+                    game.times.get(turn).map(|&x| x as i64)
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn record_run(&mut self, run: &Run) -> Result<(), Box<dyn std::error::Error>> {
+        let tx = self.conn.transaction()?;
+        let player_id = player_id(&tx, run.player.as_deref())?;
+        tx.execute(
+            "INSERT INTO runs (player_id, played_at, length, score, games)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                player_id,
+                run.played_at.map(|x| x as i64),
+                run.length as i64,
+                run.score as i64,
+                serde_json::to_string(&run.games)?
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn stats(&self, player: Option<&str>) -> Result<Stats, Box<dyn std::error::Error>> {
+        //let sqlite do the counting instead of loading every game
+        const PLAYER_FILTER: &str =
+            "(?1 IS NULL OR games.player_id = (SELECT id FROM players WHERE name = ?1))";
+        let mut stats: Stats = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*), COALESCE(SUM(won), 0), COALESCE(SUM(CASE WHEN won THEN turns ELSE 0 END), 0)
+                 FROM games WHERE {PLAYER_FILTER}"
+            ),
+            params![player],
+            |row| {
+                Ok(Stats {
+                    total_rounds: row.get(0)?,
+                    success: row.get(1)?,
+                    success_tries: row.get(2)?,
+                    ..Default::default()
+                })
+            },
+        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT UPPER(TRIM(guesses.word)), COUNT(*)
+             FROM guesses JOIN games ON games.id = guesses.game_id
+             WHERE {PLAYER_FILTER} GROUP BY 1"
+        ))?;
+        let rows = stmt.query_map(params![player], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (word, count): (String, i32) = row?;
+            stats.guess_count.insert(word, count);
+        }
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT won FROM games WHERE {PLAYER_FILTER} ORDER BY id"
+        ))?;
+        let rows = stmt.query_map(params![player], |row| row.get(0))?;
+        for row in rows {
+            stats.push_result(row?);
+        }
+        //a win's time is the one of its last guess
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT (SELECT ms FROM guesses WHERE game_id = games.id AND turn = games.turns)
+             FROM games WHERE won AND {PLAYER_FILTER} ORDER BY id"
+        ))?;
+        let rows = stmt.query_map(params![player], |row| row.get(0))?;
+        for row in rows {
+            let ms: Option<i64> = row?;
+            if let Some(x) = ms {
+                stats.push_time(x as f64 / 1000.0);
+            }
+        }
+        Ok(stats)
+    }
+
+    fn players(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM players ORDER BY name")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        let mut names = Vec::new();
+        for row in rows {
+            names.push(row?);
+        }
+        Ok(names)
+    }
+
+    fn reset_cycle(
+        &mut self,
+        player: Option<&str>,
+        at: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        //"" keys the games without a player
+        self.conn.execute(
+            "INSERT OR REPLACE INTO cycles (player, started_at) VALUES (?1, ?2)",
+            params![player.unwrap_or(""), at as i64],
+        )?;
+        Ok(())
+    }
+
+    fn past_answers(
+        &self,
+        player: Option<&str>,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        //one query, the games without a player belong to no name
+        let mut stmt = self.conn.prepare(
+            "SELECT answer FROM games
+             WHERE (CASE WHEN ?1 IS NULL THEN games.player_id IS NULL
+                    ELSE games.player_id = (SELECT id FROM players WHERE name = ?1) END)
+             AND COALESCE(played_at, 0) >=
+                 COALESCE((SELECT started_at FROM cycles WHERE player = COALESCE(?1, '')), 0)
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![player], |row| row.get(0))?;
+        let mut answers = Vec::new();
+        for row in rows {
+            answers.push(row?);
+        }
+        Ok(answers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_path;
+
+    fn game(answer: &str, guesses: &[&str], player: Option<&str>, played_at: u64) -> Game {
+        Game {
+            answer: answer.to_string(),
+            guesses: guesses.iter().map(|x| x.to_string()).collect(),
+            player: player.map(|x| x.to_string()),
+            played_at: Some(played_at),
+            mode: Some("normal".to_string()),
+            opponent: None,
+            times: (1..=guesses.len() as u64).map(|x| x * 1000).collect(),
+        }
+    }
+
+    fn fill(store: &mut dyn StateStore) {
+        store
+            .record_game(&game("CRANE", &["SLATE", "CRANE"], Some("ann"), 10))
+            .unwrap();
+        store
+            .record_game(&game("ABBEY", &["CRANE"], Some("bob"), 20))
+            .unwrap();
+        store
+            .record_game(&game("GEESE", &["GEESE"], None, 30))
+            .unwrap();
+        store
+            .record_run(&Run {
+                player: Some("ann".to_string()),
+                played_at: Some(40),
+                length: 1,
+                score: 14,
+                games: vec![game("CRANE", &["CRANE"], Some("ann"), 40)],
+            })
+            .unwrap();
+        store.reset_cycle(Some("ann"), 15).unwrap();
+    }
+
+    fn check(store: &dyn StateStore) {
+        let state = store.load().unwrap();
+        let answers: Vec<&str> = state.games.iter().map(|x| x.answer.as_str()).collect();
+        assert_eq!(answers, ["CRANE", "ABBEY", "GEESE"]);
+        assert_eq!(state.games[0].guesses, ["SLATE", "CRANE"]);
+        assert_eq!(state.games[0].times, [1000, 2000]);
+        assert_eq!(state.games[0].player.as_deref(), Some("ann"));
+        assert_eq!(state.games[2].player, None);
+        assert_eq!(state.runs.len(), 1);
+        assert_eq!(state.runs[0].score, 14);
+        assert_eq!(state.cycles.get("ann"), Some(&15));
+        assert_eq!(store.players().unwrap(), ["ann", "bob"]);
+
+        let ann = store.stats(Some("ann")).unwrap();
+        assert_eq!(
+            (ann.total_rounds, ann.success, ann.success_tries),
+            (1, 1, 2)
+        );
+        assert_eq!(ann.fastest_solve, Some(2.0));
+        let bob = store.stats(Some("bob")).unwrap();
+        assert_eq!(
+            (bob.total_rounds, bob.success, bob.current_streak),
+            (1, 0, 0)
+        );
+        assert_eq!(store.stats(None).unwrap().total_rounds, 3);
+
+        //ann's cycle restarted after their only game
+        assert!(store.past_answers(Some("ann")).unwrap().is_empty());
+        assert_eq!(store.past_answers(Some("bob")).unwrap(), ["ABBEY"]);
+        assert_eq!(store.past_answers(None).unwrap(), ["GEESE"]);
+    }
+
+    #[test]
+    fn json_store_round_trip() {
+        let path = temp_path("round-trip.json");
+        let mut store = JsonStore::create(&path).unwrap();
+        fill(&mut store);
+        check(&store);
+        //and again from the file
+        check(&JsonStore::open(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sqlite_store_round_trip() {
+        let mut store = SqliteStore::open(Path::new(":memory:")).unwrap();
+        fill(&mut store);
+        check(&store);
+    }
+
+    #[test]
+    fn copy_state_skips_games_already_there() {
+        let mut from = SqliteStore::open(Path::new(":memory:")).unwrap();
+        fill(&mut from);
+        //the same game twice is two games
+        from.record_game(&game("GEESE", &["GEESE"], None, 30))
+            .unwrap();
+        let path = temp_path("copy.json");
+        let mut to = JsonStore::create(&path).unwrap();
+        assert_eq!(copy_state(&from, &mut to).unwrap(), (4, 1));
+        assert_eq!(copy_state(&from, &mut to).unwrap(), (0, 0));
+        let state = to.load().unwrap();
+        assert_eq!(state.games.len(), 4);
+        assert_eq!(state.cycles.get("ann"), Some(&15));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn cycle_answers_restart_when_exhausted() {
+        let finals: Vec<String> = ["crane", "slate", "abbey"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        let past = |x: &[&str]| -> Vec<String> { x.iter().map(|x| x.to_string()).collect() };
+        assert_eq!(
+            cycle_answers(&past(&["CRANE", "SLATE"]), &finals),
+            ["crane", "slate"]
+        );
+        //words outside the final list and repeats don't count
+        assert_eq!(
+            cycle_answers(&past(&["CRANE", "GEESE", "CRANE"]), &finals),
+            ["crane"]
+        );
+        //all three came up, abbey starts the next cycle
+        assert_eq!(
+            cycle_answers(&past(&["CRANE", "SLATE", "ABBEY", "ABBEY"]), &finals),
+            ["abbey"]
+        );
+        assert!(cycle_answers(&past(&["SLATE", "ABBEY", "CRANE"]), &finals).is_empty());
+    }
+}
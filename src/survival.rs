@@ -0,0 +1,130 @@
+use crate::engine::MAX_TURNS;
+use crate::events::{self, Event};
+use crate::store::{self, JsonState, Run, StateStore};
+use crate::words::WordSet;
+use crate::{Cli, frontend};
+use std::collections::BTreeMap;
+
+//endless run on a shared budget of guesses: each guess spends one, a solved word gives
+//back the guesses it left unused out of MAX_TURNS, the run ends when the budget is 0
+
+pub const START_BUDGET: usize = 2 * MAX_TURNS;
+pub const SOLVE_POINTS: usize = 10; //per word, plus one per unused guess
+
+fn settle(budget: usize, used: i32) -> (usize, usize) {
+    //budget and points after a word, used <= 0 when it wasn't solved
+    if used <= 0 {
+        return (0, 0);
+    }
+    let unused = MAX_TURNS.saturating_sub(used as usize);
+    (budget - used as usize + unused, SOLVE_POINTS + unused)
+}
+
+pub fn run(
+    cli: &Cli,
+    final_list: &[String],
+    accept_list: &WordSet,
+    mut state_store: Option<Box<dyn StateStore>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (play, is_tty) = frontend(cli);
+    let mut json_data = JsonState::default(); //games of this run
+    let mut answer_list: Vec<String> = Vec::new(); //no word twice in a run
+    let mut budget = START_BUDGET;
+    let mut score = 0;
+    let mut next = cli.days.max(1) - 1;
+    loop {
+        //the seeded list from -d on, skipping words already played
+        let Some(answer) = (0..final_list.len())
+            .map(|i| &final_list[(next + i) % final_list.len()])
+            .find(|x| !answer_list.contains(x))
+            .cloned()
+        else {
+            if is_tty {
+                println!("Every word of the list is solved!");
+            }
+            break;
+        };
+        next += 1;
+        if is_tty {
+            println!(
+                "Word {}: {} guesses left, score {}",
+                answer_list.len() + 1,
+                budget,
+                score
+            );
+        }
+        let mut round_cli = cli.clone();
+        round_cli.words = Some(answer);
+        round_cli.rand_verbos = false;
+        round_cli.days = 1;
+        round_cli.seed = None;
+        round_cli.mode = Some("survival".to_string());
+        round_cli.max_turns = Some(budget);
+        let used = play(
+            &round_cli,
+            &mut answer_list,
+            &mut BTreeMap::new(),
+            final_list,
+            accept_list,
+            &mut json_data,
+            0,
+        );
+        let points;
+        (budget, points) = settle(budget, used);
+        score += points;
+        if budget == 0 {
+            break; //out of guesses
+        }
+    }
+
+    let length = json_data.games.iter().filter(|x| x.won()).count();
+    if cli.format == events::Format::Jsonl {
+        Event::Survival { length, score }.emit();
+    } else if is_tty {
+        println!("Run over: {} words solved, score {}", length, score);
+    } else {
+        println!("SURVIVAL {} {}", length, score);
+    }
+    if let Some(ref mut x) = state_store
+        && !json_data.games.is_empty()
+    {
+        x.record_run(&Run {
+            player: cli.player.clone(),
+            played_at: Some(store::now()),
+            length,
+            score,
+            games: json_data.games,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solved_words_give_back_their_unused_guesses() {
+        //3 guesses spent, 3 of 6 unused come back
+        assert_eq!(settle(START_BUDGET, 3), (START_BUDGET, SOLVE_POINTS + 3));
+        assert_eq!(settle(12, 5), (8, SOLVE_POINTS + 1));
+        assert_eq!(settle(4, 2), (6, SOLVE_POINTS + 4));
+        //past MAX_TURNS nothing comes back
+        assert_eq!(settle(9, 8), (1, SOLVE_POINTS));
+    }
+
+    #[test]
+    fn the_run_ends_at_zero() {
+        assert_eq!(settle(5, 0), (0, 0));
+        assert_eq!(settle(6, 6), (0, SOLVE_POINTS));
+        //every word takes 5 guesses, the third finds 4 left and isn't solved
+        let mut budget = START_BUDGET;
+        let mut budgets = Vec::new();
+        while budget > 0 {
+            budgets.push(budget);
+            let used = if budget >= 5 { 5 } else { 0 };
+            (budget, _) = settle(budget, used);
+        }
+        assert_eq!(budgets, [12, 8, 4]);
+    }
+}
@@ -0,0 +1,20 @@
+//helpers shared by the unit tests
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub fn temp_path(name: &str) -> PathBuf {
+    //unique per process and call, tests run in parallel
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "wordle-{}-{}-{}",
+        std::process::id(),
+        CALLS.fetch_add(1, Ordering::Relaxed),
+        name
+    ))
+}
+
+pub fn temp_file(name: &str, text: &str) -> PathBuf {
+    let path = temp_path(name);
+    std::fs::write(&path, text).unwrap();
+    path
+}
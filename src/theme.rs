@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const BUILTIN: [&str; 3] = ["dark", "light", "high-contrast"];
+
+//#rrggbb in config files
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+    fn try_from(value: String) -> Result<Rgb, String> {
+        let hex = value.trim().trim_start_matches('#');
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("bad color {}, expect #rrggbb", value));
+        }
+        let part = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Ok(Rgb(part(0), part(2), part(4)))
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(value: Rgb) -> String {
+        format!("#{:02x}{:02x}{:02x}", value.0, value.1, value.2)
+    }
+}
+
+//colors of one theme, custom ones only need the fields they change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    pub dark: bool,    //dark window background (gui)
+    pub correct: Rgb,  //'G'
+    pub present: Rgb,  //'Y'
+    pub absent: Rgb,   //'R'
+    pub unknown: Rgb,  //keys not tried yet
+    pub text: Rgb,     //letters on colored tiles
+    pub emoji: String, //share grid squares for G, Y, R
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::dark()
+    }
+}
+
+impl Palette {
+    pub fn dark() -> Palette {
+        Palette {
+            dark: true,
+            correct: Rgb(106, 170, 100),
+            present: Rgb(201, 180, 88),
+            absent: Rgb(120, 124, 126),
+            unknown: Rgb(211, 214, 218),
+            text: Rgb(255, 255, 255),
+            emoji: "🟩🟨⬛".to_string(),
+        }
+    }
+
+    pub fn light() -> Palette {
+        Palette {
+            dark: false,
+            correct: Rgb(83, 141, 78),
+            present: Rgb(181, 159, 59),
+            absent: Rgb(120, 124, 126),
+            unknown: Rgb(211, 214, 218),
+            text: Rgb(255, 255, 255),
+            emoji: "🟩🟨⬜".to_string(),
+        }
+    }
+
+    pub fn high_contrast() -> Palette {
+        //orange/blue, tells apart for red-green colorblind players
+        Palette {
+            dark: true,
+            correct: Rgb(245, 121, 58),
+            present: Rgb(133, 192, 249),
+            absent: Rgb(58, 58, 60),
+            unknown: Rgb(129, 131, 132),
+            text: Rgb(255, 255, 255),
+            emoji: "🟧🟦⬛".to_string(),
+        }
+    }
+
+    pub fn color(&self, status: char) -> Rgb {
+        match status {
+            'G' => self.correct,
+            'Y' => self.present,
+            'R' => self.absent,
+            _ => self.unknown,
+        }
+    }
+
+    pub fn emoji(&self, status: char) -> char {
+        let squares: Vec<char> = self.emoji.chars().collect();
+        let i = match status {
+            'G' => 0,
+            'Y' => 1,
+            _ => 2,
+        };
+        squares.get(i).copied().unwrap_or(['🟩', '🟨', '⬛'][i])
+    }
+}
+
+pub fn resolve(name: &str, custom: &BTreeMap<String, Palette>) -> Result<Palette, String> {
+    //custom palettes may shadow the builtin names
+    if let Some(x) = custom.get(name) {
+        return Ok(x.clone());
+    }
+    match name {
+        "dark" => Ok(Palette::dark()),
+        "light" => Ok(Palette::light()),
+        "high-contrast" => Ok(Palette::high_contrast()),
+        _ => Err(format!(
+            "unknown theme {}, expect one of {} or a palette from the config",
+            name,
+            BUILTIN.join(", ")
+        )),
+    }
+}
+
+pub fn no_color() -> bool {
+    //https://no-color.org: set and not empty
+    std::env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty())
+}
+
+pub fn share_grid(
+    feedback: &[Vec<char>],
+    won: bool,
+    max_turns: usize,
+    palette: &Palette,
+) -> String {
+    let mut out = if won {
+        format!("Wordle {}/{}\n", feedback.len(), max_turns)
+    } else {
+        format!("Wordle X/{}\n", max_turns)
+    };
+    for row in feedback.iter() {
+        out.extend(row.iter().map(|&c| palette.emoji(c)));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(Rgb::try_from("#6aaa64".to_string()), Ok(Rgb(106, 170, 100)));
+        assert_eq!(
+            Rgb::try_from(" FFffFF ".to_string()),
+            Ok(Rgb(255, 255, 255))
+        );
+        assert!(Rgb::try_from("#6aaa6".to_string()).is_err());
+        assert!(Rgb::try_from("#6aaa6g".to_string()).is_err());
+        assert_eq!(String::from(Rgb(245, 121, 8)), "#f57908");
+    }
+
+    #[test]
+    fn custom_palettes_shadow_builtin_ones() {
+        let mut custom = BTreeMap::new();
+        assert_eq!(resolve("light", &custom), Ok(Palette::light()));
+        assert!(resolve("solarized", &custom).is_err());
+        //a config palette only names what it changes
+        let dark: Palette = serde_json::from_str(r##"{"correct": "#0000ff"}"##).unwrap();
+        custom.insert("dark".to_string(), dark);
+        let palette = resolve("dark", &custom).unwrap();
+        assert_eq!(palette.correct, Rgb(0, 0, 255));
+        assert_eq!(palette.present, Palette::dark().present);
+    }
+
+    #[test]
+    fn shares_emoji_grids() {
+        let feedback = vec!["RYRRG".chars().collect(), "GGGGG".chars().collect()];
+        assert_eq!(
+            share_grid(&feedback, true, 6, &Palette::dark()),
+            "Wordle 2/6\n⬛🟨⬛⬛🟩\n🟩🟩🟩🟩🟩\n"
+        );
+        let palette = Palette {
+            emoji: "🟧".to_string(), //missing squares fall back to the defaults
+            ..Palette::high_contrast()
+        };
+        assert_eq!(
+            share_grid(&feedback[..1], false, 6, &palette),
+            "Wordle X/6\n⬛🟨⬛⬛🟧\n"
+        );
+    }
+
+    #[test]
+    fn no_color_needs_a_value() {
+        //the only test touching the environment
+        unsafe { std::env::remove_var("NO_COLOR") };
+        assert!(!no_color());
+        unsafe { std::env::set_var("NO_COLOR", "") };
+        assert!(!no_color());
+        unsafe { std::env::set_var("NO_COLOR", "1") };
+        assert!(no_color());
+        unsafe { std::env::remove_var("NO_COLOR") };
+    }
+}
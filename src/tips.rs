@@ -0,0 +1,68 @@
+use crate::words::Packed;
+use ordered_float::OrderedFloat;
+use priority_queue::PriorityQueue;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub fn entropy_ranking(pos_word_list: &[Packed], prior: Option<&[f64]>) -> Vec<(Packed, f64)> {
+    entropy_ranking_until(pos_word_list, prior, &AtomicBool::new(false)).unwrap_or_default()
+}
+
+pub fn entropy_ranking_until(
+    pos_word_list: &[Packed],
+    prior: Option<&[f64]>,
+    cancel: &AtomicBool, //set by another thread, gives up with None
+) -> Option<Vec<(Packed, f64)>> {
+    //use entropy to give recommand list, best first
+    //a prior (weight of each word) makes likely answers count more in the feedback patterns,
+    //and a word gains its chance of being the answer times the bits still unknown
+    let len = pos_word_list.len();
+    let prior = prior.filter(|x| x.iter().sum::<f64>() > 0.0);
+    let weight = |k: usize| prior.map_or(1.0, |x| x[k]);
+    let total: f64 = (0..len).map(weight).sum();
+    let unknown: f64 = (0..len)
+        .map(|k| weight(k) / total)
+        .filter(|&p| p > 0.0)
+        .map(|p| -p * f64::log2(p))
+        .sum();
+    let mut recommond_list = PriorityQueue::new();
+    for (index, i) in pos_word_list.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut entropy = 0.0;
+        let mut possible_analyse: [f64; 300] = [0.0; 300];
+        for (k, j) in pos_word_list.iter().enumerate() {
+            //visit all the possible answers
+            let mut pos_num = 0; //use ternary to condense state
+            for pos in 0..i.len() {
+                if i[pos] == j[pos] {
+                    //'G'
+                    pos_num *= 3;
+                    continue;
+                }
+                if i.contains(&j[pos]) {
+                    //'Y'
+                    pos_num = pos_num * 3 + 1;
+                    continue;
+                }
+                pos_num = pos_num * 3 + 2; //'R'
+            }
+            possible_analyse[pos_num] += weight(k);
+        }
+        for k in possible_analyse.iter().take(244) {
+            if *k > 0.0 {
+                let p = *k / total;
+                entropy += -p * f64::log2(p);
+            }
+        }
+        if prior.is_some() {
+            entropy += weight(index) / total * unknown;
+        }
+        recommond_list.push(pos_word_list[index], OrderedFloat(entropy)); //orderflofat : can compare f64
+    }
+    let mut ranking = Vec::with_capacity(len);
+    while let Some((word, entropy)) = recommond_list.pop() {
+        ranking.push((word, entropy.0));
+    }
+    Some(ranking)
+}
@@ -0,0 +1,555 @@
+use crate::engine::{MAX_TURNS, Round, WORD_LEN, clock, upper};
+use crate::race::{ClientMessage, RaceClient, RaceView};
+use crate::store::JsonState;
+use crate::theme::{self, Palette, Rgb};
+use crate::words::WordSet;
+use crate::{Cli, choose_answer, mood_mix, new_game, new_round};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::{cursor, execute, queue, terminal};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+const GRID_WIDTH: u16 = WORD_LEN as u16 * 4 - 1; //" A " tiles with one space between
+
+//raw mode + alternate screen, restored when dropped (also on panic)
+struct RawScreen;
+
+impl RawScreen {
+    fn enter() -> io::Result<RawScreen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawScreen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+pub fn read_secret(prompt: &str) -> io::Result<String> {
+    //one line typed as stars, for a word the other player mustn't see
+    let mut out = io::stdout();
+    execute!(out, Print(prompt))?;
+    terminal::enable_raw_mode()?;
+    let mut input = String::new();
+    let result = loop {
+        let Event::Key(key) = (match event::read() {
+            Ok(x) => x,
+            Err(e) => break Err(e),
+        }) else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                break Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            KeyCode::Enter => break Ok(input),
+            KeyCode::Backspace if input.pop().is_some() => {
+                let _ = execute!(out, Print("\x08 \x08"));
+            }
+            KeyCode::Char(c) if !c.is_control() => {
+                input.push(c);
+                let _ = execute!(out, Print("*"));
+            }
+            _ => {}
+        }
+    };
+    terminal::disable_raw_mode()?;
+    println!();
+    result
+}
+
+pub fn clear_screen() -> io::Result<()> {
+    //hide the scrollback of the last round from the next guesser
+    execute!(
+        io::stdout(),
+        terminal::Clear(terminal::ClearType::All),
+        terminal::Clear(terminal::ClearType::Purge),
+        cursor::MoveTo(0, 0)
+    )
+}
+
+fn rgb(color: Rgb) -> Color {
+    Color::Rgb {
+        r: color.0,
+        g: color.1,
+        b: color.2,
+    }
+}
+
+//colors of the screen, palette None with NO_COLOR
+struct Colors {
+    palette: Option<Palette>,
+    marks: bool, //status signs around tile letters, so color isn't the only signal
+}
+
+impl Colors {
+    fn fg(&self, out: &mut impl Write, color: impl Fn(&Palette) -> Color) -> io::Result<()> {
+        match self.palette {
+            Some(ref palette) => queue!(out, SetForegroundColor(color(palette))),
+            None => Ok(()),
+        }
+    }
+
+    fn tile(&self, out: &mut impl Write, letter: char, status: char) -> io::Result<()> {
+        match self.palette {
+            Some(ref palette) => queue!(
+                out,
+                SetBackgroundColor(rgb(palette.color(status))),
+                SetForegroundColor(match status {
+                    'G' | 'Y' | 'R' => rgb(palette.text),
+                    _ => Color::Black, //not tried yet
+                }),
+            )?,
+            //no color: reverse for green, underline for yellow, dim for red
+            None => match status {
+                'G' => queue!(out, SetAttribute(Attribute::Reverse))?,
+                'Y' => queue!(out, SetAttribute(Attribute::Underlined))?,
+                'R' => queue!(out, SetAttribute(Attribute::Dim))?,
+                _ => {}
+            },
+        }
+        let mark = match status {
+            'G' if self.marks => '+',
+            'Y' if self.marks => '?',
+            'R' if self.marks => '-',
+            _ => ' ',
+        };
+        queue!(
+            out,
+            SetAttribute(Attribute::Bold),
+            Print(format!("{}{}{}", mark, letter, mark)),
+            SetAttribute(Attribute::Reset),
+            ResetColor
+        )
+    }
+}
+
+fn draw(
+    out: &mut impl Write,
+    colors: &Colors,
+    round: &Round,
+    keyboard: &[String], //rows of the language pack
+    input: &str,
+    message: &str,
+) -> io::Result<()> {
+    let (width, _) = terminal::size()?;
+    let left = width.saturating_sub(GRID_WIDTH) / 2;
+    queue!(out, terminal::Clear(terminal::ClearType::All))?;
+    queue!(out, cursor::MoveTo(width.saturating_sub(6) / 2, 0),)?;
+    colors.fg(out, |x| rgb(x.correct))?;
+    queue!(
+        out,
+        SetAttribute(Attribute::Bold),
+        Print("WORDLE"),
+        SetAttribute(Attribute::Reset),
+        ResetColor
+    )?;
+
+    //fixed 6x5 grid: played rows, the row being typed, empty rows
+    //longer survival games scroll so the typed row stays visible
+    let typed = if round.finished() { 0 } else { 1 };
+    let first = (round.guesses.len() + typed).saturating_sub(MAX_TURNS);
+    for line in 0..MAX_TURNS {
+        let row = first + line;
+        queue!(out, cursor::MoveTo(left, 2 + line as u16 * 2))?;
+        for col in 0..WORD_LEN {
+            if col > 0 {
+                queue!(out, Print(" "))?;
+            }
+            if row < round.guesses.len() {
+                let letter = round.guesses[row].chars().nth(col).unwrap();
+                colors.tile(out, upper(letter), round.feedback[row][col])?;
+            } else if row == round.guesses.len() && col < input.chars().count() {
+                let letter = input.chars().nth(col).unwrap();
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Bold),
+                    Print(format!("[{}]", upper(letter))),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                colors.fg(out, |_| Color::DarkGrey)?;
+                queue!(out, Print("[ ]"), ResetColor)?;
+            }
+        }
+    }
+
+    //inline error / result line instead of INVALID
+    let message_row = 2 + MAX_TURNS as u16 * 2;
+    queue!(
+        out,
+        cursor::MoveTo(width.saturating_sub(message.len() as u16) / 2, message_row),
+    )?;
+    colors.fg(out, |_| Color::Red)?;
+    queue!(out, Print(message), ResetColor)?;
+
+    for (i, row) in keyboard.iter().enumerate() {
+        let row_width = (row.chars().count() as u16 * 4).saturating_sub(1);
+        queue!(
+            out,
+            cursor::MoveTo(
+                width.saturating_sub(row_width) / 2,
+                message_row + 2 + i as u16 * 2
+            )
+        )?;
+        for (j, key) in row.chars().enumerate() {
+            if j > 0 {
+                queue!(out, Print(" "))?;
+            }
+            colors.tile(out, key, round.letter_status(key))?;
+        }
+    }
+    let help = if colors.marks {
+        "+correct ?present -absent  Enter: submit  Esc: give up"
+    } else {
+        "Enter: submit  Backspace: delete  Esc: give up"
+    };
+    queue!(
+        out,
+        cursor::MoveTo(
+            width.saturating_sub(help.len() as u16) / 2,
+            message_row + 2 + keyboard.len() as u16 * 2
+        ),
+    )?;
+    colors.fg(out, |_| Color::DarkGrey)?;
+    queue!(out, Print(help), ResetColor)?;
+    out.flush()
+}
+
+fn draw_timer(out: &mut impl Write, timer: &str) -> io::Result<()> {
+    //top right corner, redrawn every second
+    let (width, _) = terminal::size()?;
+    queue!(
+        out,
+        cursor::MoveTo(width.saturating_sub(timer.len() as u16 + 1), 0),
+        Print(timer)
+    )?;
+    out.flush()
+}
+
+fn run(
+    round: &mut Round,
+    accept_list: &WordSet,
+    cli: &Cli,
+    times: &mut Vec<u64>,
+) -> io::Result<()> {
+    //read single keys until the round is over, the time runs out or the player gives up
+    let colors = Colors {
+        palette: (!theme::no_color()).then(|| cli.palette.clone()),
+        marks: cli.accessible || theme::no_color(),
+    };
+    let _screen = RawScreen::enter()?;
+    let mut out = io::stdout();
+    let mut input = String::new();
+    let mut message = String::new();
+    let started = Instant::now();
+    let limit = cli.time_limit.map(Duration::from_secs);
+    let mut timed_out = false;
+    loop {
+        if timed_out {
+            message = format!(
+                "Time's up! The word was {}. Press any key",
+                round.answer().to_uppercase()
+            );
+        } else if round.finished() {
+            message = if round.won() {
+                format!("You won in {} tries! Press any key", round.guesses.len())
+            } else {
+                format!(
+                    "The word was {}. Press any key",
+                    round.answer().to_uppercase()
+                )
+            };
+        }
+        draw(
+            &mut out,
+            &colors,
+            round,
+            &cli.language.keyboard,
+            &input,
+            &message,
+        )?;
+        //wait for a key, keeping the clock up to date
+        let mut shown = String::new();
+        let event = loop {
+            let elapsed = started.elapsed();
+            if !timed_out && !round.finished() {
+                if limit.is_some_and(|x| elapsed >= x) {
+                    timed_out = true;
+                    break None;
+                }
+                let timer = match limit {
+                    Some(x) => clock(x.saturating_sub(elapsed).as_secs()),
+                    None => clock(elapsed.as_secs()),
+                };
+                if timer != shown {
+                    draw_timer(&mut out, &timer)?;
+                    shown = timer;
+                }
+            }
+            if event::poll(Duration::from_millis(100))? {
+                break Some(event::read()?);
+            }
+        };
+        let Some(Event::Key(key)) = event else {
+            continue; //time's up, resize etc: just redraw
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if round.finished() || timed_out {
+            return Ok(());
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Char(c) => {
+                //folded as it's typed, a combining accent joins the letter before it
+                let typed = cli.language.normalize(&format!("{}{}", input, c));
+                if typed.chars().count() <= WORD_LEN
+                    && typed.chars().all(|x| cli.language.is_letter(x))
+                {
+                    input = typed;
+                    message.clear();
+                }
+            }
+            KeyCode::Backspace => {
+                input.pop();
+                message.clear();
+            }
+            KeyCode::Enter => match round.submit(&input, accept_list) {
+                Ok(_) => {
+                    input.clear();
+                    times.push(started.elapsed().as_millis() as u64);
+                }
+                Err(e) => message = e.message().to_string(),
+            },
+            _ => {}
+        }
+    }
+}
+
+pub fn play_tui(
+    //full-screen mood, same contract as play_tty
+    cli: &Cli,
+    answer_list: &mut Vec<String>,
+    guess_list: &mut BTreeMap<String, i32>,
+    final_list: &[String],
+    accept_list: &WordSet,
+    json_data: &mut JsonState,
+    id: usize,
+) -> i32 {
+    if mood_mix(cli) {
+        return 10000;
+    }
+    let answer_word = choose_answer(cli, answer_list, final_list, id, true); //before raw mode
+    let mut game = new_game(cli, &answer_word);
+    let mut round = new_round(cli, &answer_word);
+    if let Err(e) = run(&mut round, accept_list, cli, &mut game.times) {
+        eprintln!("terminal error: {}", e);
+    }
+
+    for word in round.guesses.iter() {
+        *guess_list.entry(word.to_uppercase()).or_insert(0) += 1;
+        game.guesses.push(word.to_uppercase());
+    }
+    let seconds = game.seconds();
+    json_data.games.push(game);
+    if !round.guesses.is_empty() {
+        print!(
+            "{}",
+            theme::share_grid(&round.feedback, round.won(), round.max_turns, &cli.palette)
+        );
+    }
+    println!("Guess turns:{}", round.guesses.len());
+    if round.won()
+        && let Some(x) = seconds
+    {
+        println!("Time:{}", clock(x as u64));
+    }
+    if !round.won() {
+        println!("Answer:{}", answer_word.to_uppercase());
+        return 0;
+    }
+    round.guesses.len() as i32
+}
+
+fn draw_opponents(out: &mut impl Write, colors: &Colors, view: &RaceView) -> io::Result<()> {
+    //left column: every opponent's colors, never their letters
+    let mut y = 2;
+    for (name, rows) in view.opponents.iter() {
+        queue!(out, cursor::MoveTo(1, y), Print(name))?;
+        for (i, row) in rows.iter().enumerate() {
+            queue!(out, cursor::MoveTo(1, y + 1 + i as u16))?;
+            for &status in row.iter() {
+                colors.tile(out, ' ', status)?;
+            }
+        }
+        y += rows.len() as u16 + 2;
+    }
+    out.flush()
+}
+
+fn draw_lobby(out: &mut impl Write, view: &RaceView) -> io::Result<()> {
+    queue!(
+        out,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(1, 0),
+        SetAttribute(Attribute::Bold),
+        Print(format!("RACE ROOM {}", view.room)),
+        SetAttribute(Attribute::Reset)
+    )?;
+    let mut y = 2;
+    for player in view.players.iter() {
+        queue!(out, cursor::MoveTo(3, y), Print(player))?;
+        y += 1;
+    }
+    if !view.standings.is_empty() {
+        y += 1;
+        queue!(out, cursor::MoveTo(1, y), Print("Last race:"))?;
+        for (i, x) in view.standings.iter().enumerate() {
+            y += 1;
+            let turns = if x.won {
+                format!("{}/{}", x.turns, MAX_TURNS)
+            } else {
+                format!("X/{}", MAX_TURNS)
+            };
+            queue!(
+                out,
+                cursor::MoveTo(3, y),
+                Print(format!("{}. {} {}", i + 1, x.player, turns))
+            )?;
+        }
+    }
+    let help = if view.racing {
+        "A race is running, wait for the next one.  Esc: leave"
+    } else {
+        "Enter: start the race  Esc: leave"
+    };
+    queue!(
+        out,
+        cursor::MoveTo(1, y + 2),
+        Print(&view.message),
+        cursor::MoveTo(1, y + 3),
+        Print(help)
+    )?;
+    out.flush()
+}
+
+fn run_race(client: &mut RaceClient, view: &mut RaceView, cli: &Cli, me: &str) -> io::Result<()> {
+    //poll the room and the keyboard in turn
+    let colors = Colors {
+        palette: (!theme::no_color()).then(|| cli.palette.clone()),
+        marks: cli.accessible || theme::no_color(),
+    };
+    let _screen = RawScreen::enter()?;
+    let mut out = io::stdout();
+    let mut input = String::new();
+    let mut in_race = false; //playing the current race, not just watching the lobby
+    let mut dirty = true; //redraw only after a message or a key
+    loop {
+        match client.poll() {
+            Ok(messages) => {
+                dirty |= !messages.is_empty();
+                for message in messages {
+                    if matches!(message, crate::race::ServerMessage::Start { .. }) {
+                        in_race = true;
+                        input.clear();
+                    }
+                    view.handle(message, me);
+                }
+            }
+            Err(e) => {
+                view.message = e;
+                return Ok(());
+            }
+        }
+        if in_race && !view.racing {
+            in_race = false; //result arrived, back to the lobby
+        }
+        if dirty && in_race {
+            draw(
+                &mut out,
+                &colors,
+                &view.round,
+                &cli.language.keyboard,
+                &input,
+                &view.message,
+            )?;
+            draw_opponents(&mut out, &colors, view)?;
+        } else if dirty {
+            draw_lobby(&mut out, view)?;
+        }
+        dirty = false;
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        dirty = true;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let sent = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Enter if !in_race && !view.racing => client.send(&ClientMessage::Start),
+            KeyCode::Char(c) if in_race && c.is_ascii_alphabetic() && input.len() < WORD_LEN => {
+                input.push(c.to_ascii_lowercase());
+                Ok(())
+            }
+            KeyCode::Backspace if in_race => {
+                input.pop();
+                Ok(())
+            }
+            KeyCode::Enter if in_race && !view.round.finished() => {
+                let word = std::mem::take(&mut input);
+                client.send(&ClientMessage::Guess { word })
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = sent {
+            view.message = e;
+            return Ok(());
+        }
+    }
+}
+
+pub fn play_race(cli: &Cli, url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    //join a room of `wordle race`, results are kept by the host
+    let me = cli
+        .player
+        .clone()
+        .ok_or("a race needs a player name, use -P")?;
+    let mut client = RaceClient::connect(url, &me)?;
+    let mut view = RaceView::default();
+    run_race(&mut client, &mut view, cli, &me)?;
+    if !view.message.is_empty() {
+        println!("{}", view.message);
+    }
+    for (i, x) in view.standings.iter().enumerate() {
+        println!(
+            "{}. {} {}",
+            i + 1,
+            x.player,
+            if x.won {
+                x.turns.to_string()
+            } else {
+                "X".to_string()
+            }
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use crate::builtin_words::{ACCEPTABLE, FINAL};
+use crate::engine::WORD_LEN;
+use crate::lang::{self, Language};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub type Packed = [u8; WORD_LEN]; //letters as indices into the alphabet
+
+//a word list packed and sorted, for binary search lookups and the entropy ranking
+#[derive(Debug, Clone, Default)]
+pub struct WordSet {
+    alphabet: Vec<char>,
+    packed: Vec<Packed>,
+}
+
+impl WordSet {
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>, alphabet: &[char]) -> WordSet {
+        let mut set = WordSet {
+            alphabet: alphabet.to_vec(),
+            packed: Vec::new(),
+        };
+        //words with letters outside the alphabet can't be guessed anyway
+        set.packed = words.into_iter().filter_map(|x| set.pack(x)).collect();
+        set.packed.sort();
+        set.packed.dedup();
+        set
+    }
+
+    pub fn pack_letter(&self, c: char) -> Option<u8> {
+        self.alphabet.iter().position(|&x| x == c).map(|i| i as u8)
+    }
+
+    pub fn pack(&self, word: &str) -> Option<Packed> {
+        let mut packed = [0; WORD_LEN];
+        let mut letters = word.chars();
+        for x in packed.iter_mut() {
+            *x = self.pack_letter(letters.next()?)?;
+        }
+        letters.next().is_none().then_some(packed)
+    }
+
+    pub fn unpack(&self, word: &Packed) -> String {
+        word.iter().map(|&i| self.alphabet[i as usize]).collect()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.pack(word)
+            .is_some_and(|x| self.packed.binary_search(&x).is_ok())
+    }
+
+    pub fn packed(&self) -> &[Packed] {
+        &self.packed
+    }
+
+    pub fn len(&self) -> usize {
+        self.packed.len()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = String> + '_ {
+        self.packed.iter().map(|x| self.unpack(x))
+    }
+}
+
+pub fn builtin_words(list: &[[u8; WORD_LEN]]) -> Vec<String> {
+    //FINAL or ACCEPTABLE as strings
+    list.iter()
+        .map(|x| String::from_utf8_lossy(x).into_owned())
+        .collect()
+}
+
+//one problem of a word list file
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub line: usize, //1-based
+    pub word: String,
+    pub problem: String,
+}
+
+pub fn read_lines(
+    path: &Path,
+    language: &Language,
+) -> Result<Vec<(usize, String)>, Box<dyn std::error::Error>> {
+    //non-empty entries, normalized as typed guesses are, with their line numbers
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    Ok(content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, language.normalize(line)))
+        .filter(|(_, word)| !word.is_empty())
+        .collect())
+}
+
+pub fn check(
+    entries: &[(usize, String)],
+    builtin: Option<&[[u8; WORD_LEN]]>, //sorted ascii, FINAL or ACCEPTABLE
+    language: &Language,
+) -> Vec<Issue> {
+    //every duplicate, entry with letters outside the alphabet, wrong length and word missing from builtin
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut issues = Vec::new();
+    for (line, word) in entries.iter() {
+        let mut problems = Vec::new();
+        if let Some(first) = seen.get(word.as_str()) {
+            problems.push(format!("duplicate of line {}", first));
+        } else {
+            seen.insert(word, *line);
+        }
+        if !word.chars().all(|c| language.is_letter(c)) {
+            problems.push("not alphabetic".to_string());
+        }
+        let letters = lang::graphemes(word).len();
+        if letters != WORD_LEN {
+            problems.push(format!("{} letters, expect {}", letters, WORD_LEN));
+        }
+        if let Some(list) = builtin
+            && <[u8; WORD_LEN]>::try_from(word.as_bytes())
+                .map_or(true, |x| list.binary_search(&x).is_err())
+        {
+            problems.push("not in the builtin list".to_string());
+        }
+        issues.extend(problems.into_iter().map(|problem| Issue {
+            line: *line,
+            word: word.clone(),
+            problem,
+        }));
+    }
+    issues
+}
+
+fn load_list(
+    path: &Path,
+    builtin: Option<&[[u8; WORD_LEN]]>, //None: custom list, any words
+    language: &Language,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let entries = read_lines(path, language)?;
+    if entries.is_empty() {
+        //empty? illegal
+        return Err(format!("{}: no words", path.display()).into());
+    }
+    let issues = check(&entries, builtin, language);
+    if let Some(x) = issues.first() {
+        let hint = if builtin.is_some() {
+            ", or --custom-lists for words of your own"
+        } else {
+            ""
+        };
+        return Err(format!(
+            "{}:{}: {}: {} ({} problems, see `wordle lists validate`{})",
+            path.display(),
+            x.line,
+            x.word,
+            x.problem,
+            issues.len(),
+            hint
+        )
+        .into());
+    }
+    let mut sorted_words: Vec<String> = entries.into_iter().map(|(_, word)| word).collect();
+    sorted_words.sort(); //letter order, so a seed gives the same answers whatever the file order
+    Ok(sorted_words)
+}
+
+pub fn load_lists(
+    final_path: Option<&Path>,  //answers, builtin FINAL when None
+    accept_path: Option<&Path>, //guesses, builtin ACCEPTABLE when None
+    custom: bool,               //files need not be subsets of the builtin lists
+    language: &Language,        //its lists replace the builtin ones
+) -> Result<(Vec<String>, WordSet), Box<dyn std::error::Error>> {
+    let final_path = final_path.or(language.final_set.as_deref());
+    let accept_path = accept_path.or(language.acceptable_set.as_deref());
+    //the builtin lists are english, a pack brings its own words
+    let custom = custom || *language != Language::english();
+    let load = |path: Option<&Path>, builtin: &[[u8; WORD_LEN]]| match path {
+        Some(x) => load_list(x, (!custom).then_some(builtin), language),
+        None if custom && *language != Language::english() => Err(format!(
+            "language pack {} has no final_set or acceptable_set",
+            language.name
+        )
+        .into()),
+        None => Ok(builtin_words(builtin)),
+    };
+    let final_list = load(final_path, FINAL)?;
+    let accept_list = load(accept_path, ACCEPTABLE)?;
+    let accept_list = WordSet::new(accept_list.iter().map(|x| x.as_str()), &language.letters());
+    if custom {
+        //the one rule left: every answer must be a word you can guess
+        let accept = &accept_list;
+        let missing: Vec<&String> = final_list.iter().filter(|x| !accept.contains(x)).collect();
+        if !missing.is_empty() {
+            let lines: HashMap<String, usize> = match final_path {
+                Some(x) => read_lines(x, language)?
+                    .into_iter()
+                    .map(|(i, w)| (w, i))
+                    .collect(),
+                None => HashMap::new(),
+            };
+            let shown: Vec<String> = missing
+                .iter()
+                .take(10)
+                .map(|x| match lines.get(x.as_str()) {
+                    Some(line) => format!("{} (line {})", x, line),
+                    None => x.to_string(),
+                })
+                .collect();
+            return Err(format!(
+                "{} answer words of {} are not in the acceptable list {}: {}{}",
+                missing.len(),
+                final_path.map_or("the builtin final set".to_string(), |x| x
+                    .display()
+                    .to_string()),
+                accept_path.map_or("(builtin)".to_string(), |x| x.display().to_string()),
+                shown.join(", "),
+                if missing.len() > shown.len() {
+                    ", ..."
+                } else {
+                    ""
+                }
+            )
+            .into());
+        }
+    }
+    Ok((final_list, accept_list))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_set_looks_up_packed_words() {
+        let alphabet: Vec<char> = ('a'..='z').collect();
+        let set = WordSet::new(["slate", "crane", "crane", "cr4ne", "cranes"], &alphabet);
+        //sorted, duplicates and words it can't pack dropped
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.words().collect::<Vec<_>>(), ["crane", "slate"]);
+        assert!(set.contains("crane") && set.contains("slate"));
+        assert!(!set.contains("trace") && !set.contains("cran") && !set.contains("CRANE"));
+        let packed = set.pack("crane").unwrap();
+        assert_eq!(packed, [2, 17, 0, 13, 4]);
+        assert_eq!(set.unpack(&packed), "crane");
+        assert_eq!(set.pack_letter('ñ'), None);
+    }
+
+    #[test]
+    fn word_set_packs_other_alphabets() {
+        let alphabet: Vec<char> = "abcdefghijklmnñopqrstuvwxyz".chars().collect();
+        let set = WordSet::new(["cañas", "niños"], &alphabet);
+        assert!(set.contains("cañas") && set.contains("niños"));
+        assert_eq!(set.pack_letter('ñ'), Some(14));
+        assert_eq!(set.unpack(&set.pack("niños").unwrap()), "niños");
+    }
+
+    #[test]
+    fn builtin_lists_are_sorted_and_looked_up() {
+        let alphabet: Vec<char> = ('a'..='z').collect();
+        let set = WordSet::new(
+            builtin_words(ACCEPTABLE).iter().map(|x| x.as_str()),
+            &alphabet,
+        );
+        assert_eq!(set.len(), ACCEPTABLE.len());
+        //every answer is a guess too
+        assert!(builtin_words(FINAL).iter().all(|x| set.contains(x)));
+        assert!(!set.contains("qqqqq"));
+        assert!(FINAL.windows(2).all(|x| x[0] < x[1]));
+    }
+
+    #[test]
+    fn load_lists_checks_custom_answers() {
+        let english = Language::english();
+        let (final_list, accept_list) = load_lists(None, None, false, &english).unwrap();
+        assert_eq!(final_list.len(), FINAL.len());
+        assert_eq!(accept_list.len(), ACCEPTABLE.len());
+        let path = crate::testutil::temp_file("answers.txt", "Slate\ncrane\n");
+        let (final_list, _) = load_lists(Some(&path), None, false, &english).unwrap();
+        assert_eq!(final_list, ["crane", "slate"]);
+        //not a builtin answer, and with --custom-lists not a guess either
+        std::fs::write(&path, "crane\nqqqqq\n").unwrap();
+        assert!(load_lists(Some(&path), None, false, &english).is_err());
+        let error = load_lists(Some(&path), None, true, &english).unwrap_err();
+        assert!(error.to_string().contains("qqqqq (line 2)"));
+        std::fs::remove_file(path).unwrap();
+    }
+}